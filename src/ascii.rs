@@ -0,0 +1,133 @@
+use crate::document::Document;
+use crate::map::{Map, Position};
+
+/// Draws the maze with plain ASCII, `+` at every corner, `--` for walls
+/// between rows and `|` for walls between columns.
+pub fn render(map: &Map) -> String {
+	let mut text = String::new();
+	for r in 0..=map.rows {
+		for c in 0..map.columns {
+			text.push('+');
+			text.push_str(if map.has_horizontal_wall(&Position(r, c)) {
+				"--"
+			} else {
+				"  "
+			});
+		}
+		text.push_str("+\n");
+
+		if r < map.rows {
+			for c in 0..=map.columns {
+				text.push(if map.has_vertical_wall(&Position(r, c)) {
+					'|'
+				} else {
+					' '
+				});
+				if c < map.columns {
+					text.push_str("  ");
+				}
+			}
+			text.push('\n');
+		}
+	}
+	text
+}
+
+/// Reads a maze drawn with `+`, `-` and `|`, with cells of any size. Anything
+/// inside the cells is ignored. Gaps in the outer wall are taken as the start
+/// and goal, in the order they're read.
+pub fn parse(text: &str) -> Result<Document, String> {
+	let lines: Vec<Vec<char>> = text
+		.lines()
+		.map(|l| l.trim_end().chars().collect::<Vec<_>>())
+		.skip_while(|l| l.is_empty())
+		.collect();
+	let lines = &lines[..lines.iter().rposition(|l| !l.is_empty()).map_or(0, |i| i + 1)];
+	let char_at = |l: usize, c: usize| lines[l].get(c).copied().unwrap_or(' ');
+	let at = |l: usize, c: usize| format!("line {}, column {}", l + 1, c + 1);
+	let broken = |at: String| format!("Expected all of a wall or none of it at {}", at);
+
+	let corner_lines: Vec<usize> = (0..lines.len()).filter(|l| char_at(*l, 0) == '+').collect();
+	let corner_columns: Vec<usize> = (0..lines.first().map_or(0, |l| l.len()))
+		.filter(|c| char_at(0, *c) == '+')
+		.collect();
+	if corner_lines.first() != Some(&0) || corner_columns.len() < 2 {
+		return Err(String::from("A maze must start with a line of corners, `+--+`"));
+	}
+	if corner_lines.len() < 2 || corner_lines[corner_lines.len() - 1] != lines.len() - 1 {
+		return Err(String::from("A maze must end with a line of corners, `+--+`"));
+	}
+
+	// Whether there's a wall along each row of corners, and between each pair
+	// of corners in the same column.
+	let mut horizontal = Vec::new();
+	for l in &corner_lines {
+		let mut walls = Vec::new();
+		for pair in corner_columns.windows(2) {
+			if let Some(c) = pair.iter().find(|c| char_at(*l, **c) != '+') {
+				return Err(format!("Expected a corner, `+`, at {}", at(*l, *c)));
+			}
+			walls.push(segment(pair[0] + 1..pair[1], |c| char_at(*l, c), '-').map_err(|c| broken(at(*l, c)))?);
+		}
+		horizontal.push(walls);
+	}
+	let mut vertical = Vec::new();
+	for pair in corner_lines.windows(2) {
+		let mut walls = Vec::new();
+		for c in &corner_columns {
+			walls.push(segment(pair[0] + 1..pair[1], |l| char_at(l, *c), '|').map_err(|l| broken(at(l, *c)))?);
+		}
+		vertical.push(walls);
+	}
+
+	let rows = corner_lines.len() - 1;
+	let columns = corner_columns.len() - 1;
+	let mut map = Map::new(rows, columns);
+	let gaps_in = |walls: &[bool], row: usize| {
+		walls
+			.iter()
+			.enumerate()
+			.filter(|(_, wall)| !**wall)
+			.map(move |(c, _)| Position(row, c))
+			.collect::<Vec<_>>()
+	};
+	let mut gaps = gaps_in(&horizontal[0], 0);
+	for (r, walls) in vertical.iter().enumerate() {
+		if !walls[0] {
+			gaps.push(Position(r, 0));
+		}
+		for (c, wall) in walls[1..columns].iter().enumerate() {
+			map.set_right(&Position(r, c), *wall);
+		}
+		if !walls[columns] {
+			gaps.push(Position(r, columns - 1));
+		}
+		if r < rows - 1 {
+			for (c, wall) in horizontal[r + 1].iter().enumerate() {
+				map.set_below(&Position(r, c), *wall);
+			}
+		}
+	}
+	gaps.extend(gaps_in(&horizontal[rows], rows - 1));
+
+	Ok(Document {
+		start: gaps.first().copied().unwrap_or(Position(0, 0)),
+		goal: gaps.get(1).copied().unwrap_or(Position(rows - 1, columns - 1)),
+		map,
+		seed: None,
+		algorithm: None,
+	})
+}
+
+/// Whether the characters along a wall are all `wall`, or all spaces. Returns
+/// the index of the first character that doesn't match the rest.
+fn segment<F>(range: std::ops::Range<usize>, char_at: F, wall: char) -> Result<bool, usize>
+where
+	F: Fn(usize) -> char,
+{
+	let closed = range.clone().next().map_or(true, |i| char_at(i) == wall);
+	match range.clone().find(|i| char_at(*i) != if closed { wall } else { ' ' }) {
+		Some(i) => Err(i),
+		None => Ok(closed),
+	}
+}
@@ -0,0 +1,226 @@
+use crate::map::{Map, Position};
+
+/// The version of the binary format written by [`Map::to_bytes`].
+const VERSION: u8 = 1;
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl Map {
+	/// Every wall in the order they're packed, first the walls below each cell
+	/// but the last row, then the walls to the right of each cell but the last
+	/// column.
+	fn walls(&self) -> impl Iterator<Item = (Position, bool)> {
+		let (rows, columns) = (self.rows, self.columns);
+		(0..rows.saturating_sub(1))
+			.flat_map(move |r| (0..columns).map(move |c| (Position(r, c), false)))
+			.chain((0..rows).flat_map(move |r| (0..columns.saturating_sub(1)).map(move |c| (Position(r, c), true))))
+	}
+
+	/// Encodes the map as a version byte, the number of rows and columns as
+	/// LEB128, and then one bit per wall, least significant bit first.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = vec![VERSION];
+		write_number(&mut bytes, self.rows);
+		write_number(&mut bytes, self.columns);
+
+		let header = bytes.len();
+		for (i, (pos, right)) in self.walls().enumerate() {
+			if i % 8 == 0 {
+				bytes.push(0);
+			}
+			let closed = if right {
+				self.is_right(&pos)
+			} else {
+				self.is_below(&pos)
+			};
+			bytes[header + i / 8] |= (closed as u8) << (i % 8);
+		}
+		bytes
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Map, String> {
+		match bytes.first() {
+			Some(&VERSION) => (),
+			Some(version) => return Err(format!("Unknown version {}", version)),
+			None => return Err(String::from("Missing version")),
+		}
+		let mut rest = &bytes[1..];
+		let rows = read_number(&mut rest)?;
+		let columns = read_number(&mut rest)?;
+		if rows == 0 || columns == 0 {
+			return Err(String::from("A maze must have at least one row and one column"));
+		}
+
+		// Checks the size before making the map, as it could be anything.
+		let count = rows
+			.checked_mul(columns)
+			.and_then(|cells| cells.checked_mul(2))
+			.map(|n| n - rows - columns)
+			.ok_or("The maze is too large")?;
+		if rest.len() != (count + 7) / 8 {
+			return Err(format!(
+				"Expected {} bytes of walls, found {}",
+				(count + 7) / 8,
+				rest.len()
+			));
+		}
+		if count % 8 != 0 && rest[rest.len() - 1] >> (count % 8) != 0 {
+			return Err(String::from("Unused bits are set"));
+		}
+
+		let mut map = Map::new(rows, columns);
+		let walls: Vec<_> = map.walls().collect();
+		for (i, (pos, right)) in walls.into_iter().enumerate() {
+			let closed = rest[i / 8] >> (i % 8) & 1 != 0;
+			if right {
+				map.set_right(&pos, closed);
+			} else {
+				map.set_below(&pos, closed);
+			}
+		}
+		Ok(map)
+	}
+
+	/// A short code for sharing the map, its bytes as unpadded base64url.
+	pub fn to_code(&self) -> String {
+		let bytes = self.to_bytes();
+		let mut code = String::with_capacity((bytes.len() * 4 + 2) / 3);
+		for chunk in bytes.chunks(3) {
+			let n = chunk
+				.iter()
+				.enumerate()
+				.fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+			for i in 0..=chunk.len() {
+				code.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+			}
+		}
+		code
+	}
+
+	pub fn from_code(code: &str) -> Result<Map, String> {
+		let sextets = code
+			.trim()
+			.trim_end_matches('=')
+			.chars()
+			.map(|c| {
+				BASE64URL
+					.iter()
+					.position(|b| *b as char == c)
+					.ok_or_else(|| format!("Unexpected character '{}' in the code", c))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		if sextets.len() % 4 == 1 {
+			return Err(String::from("The code has the wrong length"));
+		}
+
+		let mut bytes = Vec::with_capacity(sextets.len() * 3 / 4);
+		for chunk in sextets.chunks(4) {
+			let n = chunk
+				.iter()
+				.enumerate()
+				.fold(0u32, |n, (i, s)| n | (*s as u32) << (18 - 6 * i));
+			for i in 0..chunk.len() - 1 {
+				bytes.push((n >> (16 - 8 * i)) as u8);
+			}
+		}
+		Map::from_bytes(&bytes)
+	}
+}
+
+fn write_number(bytes: &mut Vec<u8>, mut n: usize) {
+	while n >= 0x80 {
+		bytes.push(n as u8 | 0x80);
+		n >>= 7;
+	}
+	bytes.push(n as u8);
+}
+
+fn read_number(bytes: &mut &[u8]) -> Result<usize, String> {
+	let mut n = 0usize;
+	for shift in (0..usize::BITS).step_by(7) {
+		let (byte, rest) = bytes.split_first().ok_or("The code ends too early")?;
+		*bytes = rest;
+		n |= ((byte & 0x7f) as usize) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(n);
+		}
+	}
+	Err(String::from("A number in the code is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use crate::map::ALGORITHMS;
+
+	#[test]
+	fn reads_back_codes_of_generated_maps() {
+		for algorithm in &ALGORITHMS {
+			// 200 columns take two bytes to write.
+			for (rows, columns) in &[(1, 1), (1, 9), (9, 1), (5, 7), (8, 8), (2, 200)] {
+				for seed in 0..3 {
+					let mut rng = StdRng::seed_from_u64(seed);
+					let map = Map::generate(
+						*algorithm,
+						*rows,
+						*columns,
+						Position(0, 0),
+						&mut rng,
+						|_| {},
+						|_, _, _| {},
+					);
+					let code = map.to_code();
+					assert!(code.bytes().all(|b| BASE64URL.contains(&b)), "{}", code);
+					assert!(Map::from_code(&code).as_ref() == Ok(&map), "{}", code);
+					assert!(Map::from_bytes(&map.to_bytes()).as_ref() == Ok(&map), "{}", code);
+				}
+			}
+		}
+	}
+
+	fn error_of(bytes: &[u8]) -> String {
+		Map::from_bytes(bytes)
+			.err()
+			.expect("The bytes should have been rejected")
+	}
+
+	#[test]
+	fn rejects_malformed_bytes() {
+		assert_eq!(error_of(&[]), "Missing version");
+		assert_eq!(error_of(&[2, 1, 1]), "Unknown version 2");
+		assert_eq!(error_of(&[VERSION, 3]), "The code ends too early");
+		assert_eq!(
+			error_of(&[VERSION, 0, 3]),
+			"A maze must have at least one row and one column"
+		);
+		let mut large = vec![0xff; 12];
+		large[0] = VERSION;
+		assert_eq!(error_of(&large), "A number in the code is too large");
+
+		// Two by two has four walls, which fit in one byte.
+		assert_eq!(error_of(&[VERSION, 2, 2]), "Expected 1 bytes of walls, found 0");
+		assert_eq!(error_of(&[VERSION, 2, 2, 0, 0]), "Expected 1 bytes of walls, found 2");
+		assert_eq!(error_of(&[VERSION, 2, 2, 0x10]), "Unused bits are set");
+		assert!(Map::from_bytes(&[VERSION, 2, 2, 0x0f]).is_ok());
+	}
+
+	#[test]
+	fn rejects_malformed_codes() {
+		// Five bytes, which take seven characters.
+		let code = Map::new(3, 3).to_code();
+		assert_eq!(code.len(), 7);
+		assert_eq!(
+			Map::from_code(&format!("{}+", &code[1..])).err().as_deref(),
+			Some("Unexpected character '+' in the code")
+		);
+		assert_eq!(
+			Map::from_code(&format!("{}AA", code)).err().as_deref(),
+			Some("The code has the wrong length")
+		);
+		assert!(Map::from_code(&format!(" {}==\n", code)).as_ref() == Ok(&Map::new(3, 3)));
+	}
+}
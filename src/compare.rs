@@ -0,0 +1,126 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use std::fmt;
+use std::time::Instant;
+
+use crate::map::{Algorithm, Map, Position, ALGORITHMS};
+
+/// Mean and variance of a metric over all samples.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+	pub mean: f64,
+	pub variance: f64,
+}
+
+impl Summary {
+	fn of(values: &[f64]) -> Summary {
+		if values.is_empty() {
+			return Summary::default();
+		}
+
+		let n = values.len() as f64;
+		let mean = values.iter().sum::<f64>() / n;
+		let variance = if values.len() > 1 {
+			values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0)
+		} else {
+			0.0
+		};
+		Summary { mean, variance }
+	}
+}
+
+pub struct Comparison {
+	pub algorithm: Algorithm,
+	pub dead_ends: Summary,
+	pub solution_length: Summary,
+	pub branching_factor: Summary,
+	/// In milliseconds.
+	pub generation_time: Summary,
+}
+
+pub struct Report {
+	pub rows: usize,
+	pub columns: usize,
+	pub samples: usize,
+	pub seed: u64,
+	pub comparisons: Vec<Comparison>,
+}
+
+/// Generates `samples` mazes with every algorithm. Sample `i` is seeded with
+/// `seed + i` for all algorithms, so a report can be reproduced.
+pub fn compare(rows: usize, columns: usize, samples: usize, seed: u64) -> Report {
+	let comparisons = ALGORITHMS
+		.iter()
+		.map(|algorithm| {
+			let mut dead_ends = Vec::with_capacity(samples);
+			let mut solution_length = Vec::with_capacity(samples);
+			let mut branching_factor = Vec::with_capacity(samples);
+			let mut generation_time = Vec::with_capacity(samples);
+
+			for i in 0..samples {
+				let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+				let start = Instant::now();
+				let map = Map::generate(
+					*algorithm,
+					rows,
+					columns,
+					Position(0, 0),
+					&mut rng,
+					|_| {},
+					|_, _, _| {},
+				);
+				generation_time.push(start.elapsed().as_secs_f64() * 1000.0);
+
+				let stats = map.stats();
+				dead_ends.push(stats.dead_ends as f64);
+				solution_length.push(stats.solution.map(|s| s.length).unwrap_or(0) as f64);
+				branching_factor.push(stats.branching_factor);
+			}
+
+			Comparison {
+				algorithm: *algorithm,
+				dead_ends: Summary::of(&dead_ends),
+				solution_length: Summary::of(&solution_length),
+				branching_factor: Summary::of(&branching_factor),
+				generation_time: Summary::of(&generation_time),
+			}
+		})
+		.collect();
+
+	Report {
+		rows,
+		columns,
+		samples,
+		seed,
+		comparisons,
+	}
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(
+			f,
+			"{}×{} maze, {} samples per algorithm, seed {}",
+			self.rows, self.columns, self.samples, self.seed
+		)?;
+		writeln!(f)?;
+		writeln!(
+			f,
+			"{:<10}{:>24}{:>24}{:>24}{:>24}",
+			"", "dead ends", "solution length", "branching factor", "generation time (ms)"
+		)?;
+		write!(f, "{:<10}", "algorithm")?;
+		for _ in 0..4 {
+			write!(f, "{:>12}{:>12}", "mean", "variance")?;
+		}
+		for c in &self.comparisons {
+			writeln!(f)?;
+			write!(f, "{:<10}", c.algorithm.name())?;
+			for s in &[c.dead_ends, c.solution_length, c.branching_factor, c.generation_time] {
+				write!(f, "{:>12.4}{:>12.4}", s.mean, s.variance)?;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,100 @@
+use crate::grid::{Shape, Side};
+use crate::map::Position;
+
+/// The height of a triangle with sides one cell long.
+const HEIGHT: f64 = 0.866_025_403_784_438_6;
+
+/// Rows of triangles pointing up and down in turn, starting with one pointing
+/// up in the upper left corner. Triangles next to each other overlap by half
+/// a cell.
+pub struct Delta {
+	rows: usize,
+	columns: usize,
+}
+
+impl Delta {
+	/// Triangles in a single column only touch the ones two rows away at a
+	/// corner, so it takes two columns for more than two rows.
+	pub fn new(rows: usize, columns: usize) -> Result<Delta, String> {
+		if columns < 2 && rows > 2 {
+			return Err(String::from(
+				"A triangle grid needs at least 2 columns for more than 2 rows",
+			));
+		}
+		Ok(Delta { rows, columns })
+	}
+
+	pub fn position(&self, cell: usize) -> Position {
+		Position(cell / self.columns, cell % self.columns)
+	}
+
+	pub fn points_up(&self, pos: &Position) -> bool {
+		(pos.0 + pos.1) % 2 == 0
+	}
+
+	/// The neighbouring cells, left, right and then the one sharing the flat
+	/// side, above triangles pointing down and below those pointing up.
+	fn neighbours_of(&self, pos: &Position) -> [Option<Position>; 3] {
+		let Position(r, c) = *pos;
+		let flat = if self.points_up(pos) {
+			Some(Position(r + 1, c))
+		} else {
+			r.checked_sub(1).map(|r| Position(r, c))
+		};
+		[c.checked_sub(1).map(|c| Position(r, c)), Some(Position(r, c + 1)), flat]
+	}
+}
+
+impl Shape for Delta {
+	fn count(&self) -> usize {
+		self.rows * self.columns
+	}
+
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)> {
+		let pos = self.position(cell);
+		let [left, right, flat] = self.neighbours_of(&pos);
+		let [left, right, flat] = [left, right, flat].map(|p| p.and_then(|p| self.cell(&p)));
+		let x = pos.1 as f64 * 0.5;
+		let (top, bottom) = (pos.0 as f64 * HEIGHT, (pos.0 + 1) as f64 * HEIGHT);
+		if self.points_up(&pos) {
+			let (apex, right_corner, left_corner) = ((x + 0.5, top), (x + 1.0, bottom), (x, bottom));
+			vec![
+				(Side::Line(apex, right_corner), right),
+				(Side::Line(right_corner, left_corner), flat),
+				(Side::Line(left_corner, apex), left),
+			]
+		} else {
+			let (left_corner, right_corner, apex) = ((x, top), (x + 1.0, top), (x + 0.5, bottom));
+			vec![
+				(Side::Line(left_corner, right_corner), flat),
+				(Side::Line(right_corner, apex), right),
+				(Side::Line(apex, left_corner), left),
+			]
+		}
+	}
+
+	/// The middle of the triangle, a third of the way from its flat side.
+	fn center(&self, cell: usize) -> (f64, f64) {
+		let pos = self.position(cell);
+		let third = if self.points_up(&pos) { 2.0 } else { 1.0 };
+		(pos.1 as f64 * 0.5 + 0.5, (pos.0 as f64 + third / 3.0) * HEIGHT)
+	}
+
+	fn size(&self) -> (f64, f64) {
+		((self.columns + 1) as f64 * 0.5, self.rows as f64 * HEIGHT)
+	}
+
+	fn cell(&self, pos: &Position) -> Option<usize> {
+		Some(pos.0 * self.columns + pos.1).filter(|_| pos.0 < self.rows && pos.1 < self.columns)
+	}
+
+	fn arrow(&self, from: usize, to: usize) -> char {
+		let (from, to) = (self.position(from), self.position(to));
+		match (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize) {
+			(-1, _) => '↑',
+			(1, _) => '↓',
+			(_, -1) => '←',
+			_ => '→',
+		}
+	}
+}
@@ -0,0 +1,33 @@
+use crate::map::{Algorithm, Map, Position};
+
+/// A maze together with how it was made and where it should be solved, as
+/// described by `docs/maze.schema.json`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub struct Document {
+	#[cfg_attr(feature = "serde", serde(flatten))]
+	pub map: Map,
+	#[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+	pub seed: Option<u64>,
+	#[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+	pub algorithm: Option<Algorithm>,
+	pub start: Position,
+	pub goal: Position,
+}
+
+#[cfg(feature = "serde")]
+impl Document {
+	pub fn to_json(&self) -> String {
+		serde_json::to_string(self).unwrap()
+	}
+
+	pub fn from_json(json: &str) -> Result<Document, String> {
+		let document: Document = serde_json::from_str(json).map_err(|e| e.to_string())?;
+		for (name, pos) in &[("start", document.start), ("goal", document.goal)] {
+			if pos.0 >= document.map.rows || pos.1 >= document.map.columns || document.map.is_masked(pos) {
+				return Err(format!("The {} ({}, {}) is outside the maze", name, pos.0, pos.1));
+			}
+		}
+		Ok(document)
+	}
+}
@@ -0,0 +1,58 @@
+use crate::map::Map;
+
+/// How to draw a maze, shared by the vector and raster renderers. Sizes are in
+/// pixels, colors are CSS colors.
+#[derive(Clone)]
+pub struct DrawOptions {
+	/// Width and height of a cell.
+	pub cell_size: f64,
+	pub wall_thickness: f64,
+	/// Empty space around the maze.
+	pub margin: f64,
+	pub wall_color: String,
+	pub background: String,
+	pub solution_color: String,
+	pub start_color: String,
+	pub goal_color: String,
+	/// Cells are filled with this color, more opaque the further they are
+	/// from the start.
+	pub heatmap_color: String,
+	pub solution: bool,
+	pub markers: bool,
+	pub heatmap: bool,
+}
+
+impl Default for DrawOptions {
+	fn default() -> Self {
+		DrawOptions {
+			cell_size: 20.0,
+			wall_thickness: 2.0,
+			margin: 10.0,
+			wall_color: "black".to_string(),
+			background: "white".to_string(),
+			solution_color: "red".to_string(),
+			start_color: "green".to_string(),
+			goal_color: "blue".to_string(),
+			heatmap_color: "orange".to_string(),
+			solution: false,
+			markers: false,
+			heatmap: false,
+		}
+	}
+}
+
+impl DrawOptions {
+	pub fn width(&self, map: &Map) -> f64 {
+		map.columns as f64 * self.cell_size + 2.0 * self.margin
+	}
+	pub fn height(&self, map: &Map) -> f64 {
+		map.rows as f64 * self.cell_size + 2.0 * self.margin
+	}
+
+	pub fn x(&self, column: usize) -> f64 {
+		self.margin + column as f64 * self.cell_size
+	}
+	pub fn y(&self, row: usize) -> f64 {
+		self.margin + row as f64 * self.cell_size
+	}
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::png::BitWriter;
+use crate::raster::{Color, Image};
+
+const MAX_CODES: u16 = 4096;
+
+/// Encodes an animated GIF one frame at a time. Only the part of each frame
+/// that differs from the previous one is stored.
+pub struct GifEncoder {
+	width: usize,
+	height: usize,
+	palette: Vec<Color>,
+	indices: HashMap<Color, u8>,
+	/// Each frame's delay, in hundredths of a second, and its encoded image.
+	frames: Vec<(u16, Vec<u8>)>,
+	previous: Option<Image>,
+}
+
+impl GifEncoder {
+	pub fn new(width: usize, height: usize) -> GifEncoder {
+		GifEncoder {
+			width,
+			height,
+			palette: Vec::new(),
+			indices: HashMap::new(),
+			frames: Vec::new(),
+			previous: None,
+		}
+	}
+
+	/// Adds a frame that's shown for `delay` hundredths of a second.
+	pub fn add_frame(&mut self, image: &Image, delay: u16) {
+		assert!(image.width == self.width && image.height == self.height);
+
+		let (left, top, right, bottom) = match &self.previous {
+			None => (0, 0, self.width, self.height),
+			Some(previous) => match changed_area(previous, image) {
+				Some(area) => area,
+				None => {
+					if let Some(last) = self.frames.last_mut() {
+						last.0 = last.0.saturating_add(delay);
+					}
+					return;
+				}
+			},
+		};
+
+		let mut indices = Vec::with_capacity((right - left) * (bottom - top));
+		for y in top..bottom {
+			for x in left..right {
+				indices.push(self.index_of(image.pixels[y * self.width + x]));
+			}
+		}
+
+		let mut frame = Vec::new();
+		for v in &[left, top, right - left, bottom - top] {
+			frame.extend_from_slice(&(*v as u16).to_le_bytes());
+		}
+		// No local color table, not interlaced.
+		frame.push(0);
+		frame.push(8);
+		for block in lzw(&indices, 8).chunks(255) {
+			frame.push(block.len() as u8);
+			frame.extend_from_slice(block);
+		}
+		frame.push(0);
+
+		self.frames.push((delay, frame));
+		self.previous = Some(image.clone());
+	}
+
+	pub fn finish(self) -> Vec<u8> {
+		let table_bits = (1..=8).find(|b| 1 << b >= self.palette.len()).unwrap_or(8);
+
+		let mut gif = b"GIF89a".to_vec();
+		gif.extend_from_slice(&(self.width as u16).to_le_bytes());
+		gif.extend_from_slice(&(self.height as u16).to_le_bytes());
+		// Global color table, 8 bits per primary color.
+		gif.push(0xf0 | (table_bits - 1) as u8);
+		gif.push(0);
+		gif.push(0);
+		for i in 0..1 << table_bits {
+			gif.extend_from_slice(self.palette.get(i).unwrap_or(&[0, 0, 0]));
+		}
+
+		// Loop forever.
+		gif.extend_from_slice(&[0x21, 0xff, 0x0b]);
+		gif.extend_from_slice(b"NETSCAPE2.0");
+		gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+		for (delay, frame) in self.frames {
+			// Graphic control extension, leave the frame in place when moving
+			// on to the next.
+			gif.extend_from_slice(&[0x21, 0xf9, 0x04, 0x04]);
+			gif.extend_from_slice(&delay.to_le_bytes());
+			gif.extend_from_slice(&[0x00, 0x00]);
+			gif.push(0x2c);
+			gif.extend(frame);
+		}
+
+		gif.push(0x3b);
+		gif
+	}
+
+	/// The palette index of `color`, once the palette is full the closest
+	/// color is used instead.
+	fn index_of(&mut self, color: Color) -> u8 {
+		if let Some(index) = self.indices.get(&color) {
+			return *index;
+		}
+
+		let index = if self.palette.len() < 256 {
+			self.palette.push(color);
+			(self.palette.len() - 1) as u8
+		} else {
+			let distance = |p: &Color| (0..3).map(|i| (p[i] as i32 - color[i] as i32).pow(2)).sum::<i32>();
+			(0..self.palette.len())
+				.min_by_key(|i| distance(&self.palette[*i]))
+				.unwrap() as u8
+		};
+		self.indices.insert(color, index);
+		index
+	}
+}
+
+/// The smallest rectangle, as left, top, right and bottom, containing every
+/// pixel that differs between the images.
+fn changed_area(a: &Image, b: &Image) -> Option<(usize, usize, usize, usize)> {
+	let mut area: Option<(usize, usize, usize, usize)> = None;
+	for y in 0..a.height {
+		for x in 0..a.width {
+			if a.pixels[y * a.width + x] != b.pixels[y * b.width + x] {
+				area = Some(match area {
+					None => (x, y, x + 1, y + 1),
+					Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(x + 1), b.max(y + 1)),
+				});
+			}
+		}
+	}
+	area
+}
+
+/// Compresses palette indices with GIF's variable code size LZW.
+fn lzw(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+	let clear = 1u16 << min_code_size;
+	let end = clear + 1;
+
+	let mut writer = BitWriter::default();
+	let mut code_size = min_code_size + 1;
+	let mut next = end + 1;
+	let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+
+	writer.write_bits(clear as u32, code_size);
+	let mut current: Option<u16> = None;
+	for index in indices {
+		let prefix = match current {
+			None => {
+				current = Some(*index as u16);
+				continue;
+			}
+			Some(prefix) => prefix,
+		};
+		if let Some(code) = table.get(&(prefix, *index)) {
+			current = Some(*code);
+			continue;
+		}
+
+		writer.write_bits(prefix as u32, code_size);
+		if next < MAX_CODES {
+			table.insert((prefix, *index), next);
+			next += 1;
+			if next - 1 == 1 << code_size {
+				code_size += 1;
+			}
+		} else {
+			writer.write_bits(clear as u32, code_size);
+			table.clear();
+			next = end + 1;
+			code_size = min_code_size + 1;
+		}
+		current = Some(*index as u16);
+	}
+
+	if let Some(code) = current {
+		writer.write_bits(code as u32, code_size);
+		// The decoder adds an entry for this code before reading the end.
+		if next == 1 << code_size && code_size < 12 {
+			code_size += 1;
+		}
+	}
+	writer.write_bits(end as u32, code_size);
+	writer.finish()
+}
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::map::{Map, Position};
+
+/// Cells are numbered row by row, from zero.
+fn id(map: &Map, pos: &Position) -> usize {
+	pos.0 * map.columns + pos.1
+}
+
+/// The passages as a Graphviz graph, with every node pinned to its cell. Lay
+/// it out with `neato -n` or `fdp` to keep the positions.
+pub fn dot(map: &Map, start: Position, goal: Position) -> String {
+	let mut dot = String::from("graph maze {\n");
+	dot.push_str("\tnode [shape=circle, width=0.3, fixedsize=true, fontsize=8];\n");
+	for pos in map.cells() {
+		write!(
+			dot,
+			"\t{} [label=\"{},{}\", pos=\"{},{}!\"",
+			id(map, &pos),
+			pos.0,
+			pos.1,
+			pos.1 * 36,
+			(map.rows - 1 - pos.0) * 36
+		)
+		.unwrap();
+		if pos == start {
+			dot.push_str(", style=filled, fillcolor=green");
+		} else if pos == goal {
+			dot.push_str(", style=filled, fillcolor=blue, fontcolor=white");
+		}
+		dot.push_str("];\n");
+	}
+	for (a, b) in map.passages() {
+		writeln!(dot, "\t{} -- {};", id(map, &a), id(map, &b)).unwrap();
+	}
+	dot.push_str("}\n");
+	dot
+}
+
+/// The passages as an undirected GraphML graph, with the row and column of
+/// every node, and whether it's the start or goal.
+pub fn graphml(map: &Map, start: Position, goal: Position) -> String {
+	let mut xml = String::from(concat!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+		"\n",
+		r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+		"\n",
+		r#"<key id="row" for="node" attr.name="row" attr.type="int"/>"#,
+		"\n",
+		r#"<key id="column" for="node" attr.name="column" attr.type="int"/>"#,
+		"\n",
+		r#"<key id="role" for="node" attr.name="role" attr.type="string"/>"#,
+		"\n",
+		r#"<graph id="maze" edgedefault="undirected">"#,
+		"\n",
+	));
+	for pos in map.cells() {
+		write!(
+			xml,
+			r#"<node id="n{}"><data key="row">{}</data><data key="column">{}</data>"#,
+			id(map, &pos),
+			pos.0,
+			pos.1
+		)
+		.unwrap();
+		if pos == start {
+			xml.push_str(r#"<data key="role">start</data>"#);
+		} else if pos == goal {
+			xml.push_str(r#"<data key="role">goal</data>"#);
+		}
+		xml.push_str("</node>\n");
+	}
+	for (a, b) in map.passages() {
+		writeln!(xml, r#"<edge source="n{}" target="n{}"/>"#, id(map, &a), id(map, &b)).unwrap();
+	}
+	xml.push_str("</graph>\n</graphml>\n");
+	xml
+}
+
+/// The passages one per line, as the numbers of the two cells.
+pub fn edge_list(map: &Map) -> String {
+	map.passages()
+		.iter()
+		.map(|(a, b)| format!("{} {}\n", id(map, a), id(map, b)))
+		.collect()
+}
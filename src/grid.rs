@@ -0,0 +1,581 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+use crate::draw::DrawOptions;
+use crate::map::{Algorithm, Position};
+use crate::raster::{color_of, Image};
+
+/// A side of a cell, in cell widths from the upper left corner of the grid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Side {
+	Line((f64, f64), (f64, f64)),
+	/// Part of a circle, between two angles in radians. Angles go clockwise
+	/// from the right, as the y axis points down.
+	Arc {
+		center: (f64, f64),
+		radius: f64,
+		from: f64,
+		to: f64,
+	},
+}
+
+impl Side {
+	fn start(&self) -> (f64, f64) {
+		match self {
+			Side::Line(from, _) => *from,
+			Side::Arc {
+				center, radius, from, ..
+			} => on_circle(*center, *radius, *from),
+		}
+	}
+
+	/// The side as an SVG path command, continuing from its start.
+	fn segment(&self, scale: &impl Fn((f64, f64)) -> (f64, f64)) -> String {
+		match self {
+			Side::Line(_, to) => {
+				let to = scale(*to);
+				format!("L{:.2} {:.2}", to.0, to.1)
+			}
+			Side::Arc {
+				center,
+				radius,
+				from,
+				to,
+			} => {
+				let scaled = distance(scale(*center), scale(on_circle(*center, *radius, *from)));
+				// A single arc command can't go all the way around, so larger
+				// arcs are drawn in two halves.
+				let halves = if (to - from).abs() >= std::f64::consts::PI {
+					vec![(from + to) / 2.0, *to]
+				} else {
+					vec![*to]
+				};
+				halves
+					.into_iter()
+					.map(|angle| {
+						let (x, y) = scale(on_circle(*center, *radius, angle));
+						format!(
+							"A{:.2} {:.2} 0 0 {} {:.2} {:.2}",
+							scaled,
+							scaled,
+							if to > from { 1 } else { 0 },
+							x,
+							y
+						)
+					})
+					.collect()
+			}
+		}
+	}
+
+	/// The side as an SVG path, starting with a move to its start.
+	fn path(&self, scale: &impl Fn((f64, f64)) -> (f64, f64)) -> String {
+		let from = scale(self.start());
+		format!("M{:.2} {:.2}{}", from.0, from.1, self.segment(scale))
+	}
+
+	/// Points along the side, close enough together that arcs look round when
+	/// drawn as straight lines between them.
+	fn points(&self) -> Vec<(f64, f64)> {
+		match self {
+			Side::Line(from, to) => vec![*from, *to],
+			Side::Arc {
+				center,
+				radius,
+				from,
+				to,
+			} => {
+				let steps = ((to - from).abs() * radius * 8.0).ceil().max(2.0) as usize;
+				(0..=steps)
+					.map(|i| on_circle(*center, *radius, from + (to - from) * i as f64 / steps as f64))
+					.collect()
+			}
+		}
+	}
+}
+
+/// The point at an angle on a circle.
+pub fn on_circle(center: (f64, f64), radius: f64, angle: f64) -> (f64, f64) {
+	(center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+	(a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// The cells of a grid of any shape, and how they lie next to each other.
+/// Cells are numbered from 0 up to [`Shape::count`].
+pub trait Shape {
+	/// Whether the start is entered from outside, through a gap in the border.
+	const ENTRANCE: bool = false;
+
+	/// The number of cells.
+	fn count(&self) -> usize;
+
+	/// The sides of the cell going around it, each with the cell on the other
+	/// side of it if it isn't on the border.
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)>;
+
+	/// The cells next to `cell`.
+	fn neighbours(&self, cell: usize) -> Vec<usize> {
+		self.sides(cell).into_iter().filter_map(|(_, other)| other).collect()
+	}
+
+	/// The middle of the cell, in cell widths.
+	fn center(&self, cell: usize) -> (f64, f64);
+
+	/// Width and height of the grid, in cell widths.
+	fn size(&self) -> (f64, f64);
+
+	/// The cell at a row and a column, if there's one.
+	fn cell(&self, pos: &Position) -> Option<usize>;
+
+	/// The default start and goal.
+	fn ends(&self) -> (usize, usize) {
+		(0, self.count() - 1)
+	}
+
+	/// The arrow for stepping from a cell to one next to it.
+	fn arrow(&self, from: usize, to: usize) -> char;
+}
+
+/// A maze on a grid of some shape, as the passages between its cells.
+pub struct Grid<S: Shape> {
+	pub shape: S,
+	/// Pairs of neighbouring cells without a wall between them, the lower
+	/// cell first.
+	open: HashSet<(usize, usize)>,
+}
+
+impl<S: Shape> Grid<S> {
+	/// A grid with all walls closed.
+	pub fn new(shape: S) -> Grid<S> {
+		Grid {
+			shape,
+			open: HashSet::new(),
+		}
+	}
+
+	pub fn is_open(&self, a: usize, b: usize) -> bool {
+		self.open.contains(&(a.min(b), a.max(b)))
+	}
+
+	pub fn set(&mut self, a: usize, b: usize, closed: bool) {
+		if closed {
+			self.open.remove(&(a.min(b), a.max(b)));
+		} else {
+			self.open.insert((a.min(b), a.max(b)));
+		}
+	}
+
+	/// The neighbouring cells that can be reached from `cell`.
+	pub fn openings(&self, cell: usize) -> Vec<usize> {
+		self.shape
+			.neighbours(cell)
+			.into_iter()
+			.filter(|other| self.is_open(cell, *other))
+			.collect()
+	}
+
+	/// Generates a maze on the shape. The binary tree and recursive division
+	/// only work on square grids.
+	pub fn generate<R>(algorithm: Algorithm, shape: S, start: usize, rng: &mut R) -> Result<Grid<S>, String>
+	where
+		R: Rng + ?Sized,
+	{
+		let mut grid = Grid::new(shape);
+		grid.carve(algorithm, start, rng, |_, _, _| {})?;
+		Ok(grid)
+	}
+
+	/// Carves a maze out of the grid, calling `peek` with the cells on both
+	/// sides of every wall that's opened or closed. Kruskal's algorithm keeps
+	/// the passages that are already open, and joins up the parts they make.
+	pub fn carve<R, P>(&mut self, algorithm: Algorithm, start: usize, rng: &mut R, mut peek: P) -> Result<(), String>
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		match algorithm {
+			Algorithm::Dfs => self.generate_dfs(start, rng, &mut peek),
+			Algorithm::Prim => self.generate_prim(start, rng, &mut peek),
+			Algorithm::AldousBroder => self.generate_ab(start, rng, &mut peek),
+			Algorithm::Wilson => self.generate_wilson(start, rng, &mut peek),
+			Algorithm::Kruskal => self.generate_kruskal(rng, &mut peek),
+			Algorithm::Tree | Algorithm::Division => return Err(format!("{} only works on square grids", algorithm)),
+		}
+		Ok(())
+	}
+
+	fn carve_wall<P: FnMut(&Grid<S>, usize, usize)>(&mut self, a: usize, b: usize, closed: bool, peek: &mut P) {
+		self.set(a, b, closed);
+		peek(self, a, b);
+	}
+
+	fn generate_dfs<R, P>(&mut self, start: usize, rng: &mut R, peek: &mut P)
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		let mut visited = vec![false; self.shape.count()];
+		visited[start] = true;
+		let mut to_visit = vec![start];
+		while let Some(next) = to_visit.pop() {
+			let unvisited: Vec<_> = self
+				.shape
+				.neighbours(next)
+				.into_iter()
+				.filter(|other| !visited[*other])
+				.collect();
+			if unvisited.len() > 1 {
+				to_visit.push(next);
+			}
+			if let Some(moved) = unvisited.choose(rng) {
+				self.carve_wall(next, *moved, false, peek);
+				visited[*moved] = true;
+				to_visit.push(*moved);
+			}
+		}
+	}
+
+	fn generate_prim<R, P>(&mut self, start: usize, rng: &mut R, peek: &mut P)
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		let mut visited = vec![false; self.shape.count()];
+		visited[start] = true;
+		let mut walls: Vec<_> = self.shape.neighbours(start).into_iter().map(|n| (start, n)).collect();
+		while !walls.is_empty() {
+			let (from, to) = walls.remove(rng.gen_range(0, walls.len()));
+			if !visited[to] {
+				self.carve_wall(from, to, false, peek);
+				visited[to] = true;
+				walls.extend(self.shape.neighbours(to).into_iter().map(|n| (to, n)));
+			}
+		}
+	}
+
+	fn generate_ab<R, P>(&mut self, start: usize, rng: &mut R, peek: &mut P)
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		let mut visited = vec![false; self.shape.count()];
+		visited[start] = true;
+		let mut remaining = self.shape.count() - 1;
+		let mut current = start;
+		while remaining > 0 {
+			let moved = *self.shape.neighbours(current).choose(rng).unwrap();
+			if !visited[moved] {
+				self.carve_wall(current, moved, false, peek);
+				visited[moved] = true;
+				remaining -= 1;
+			}
+			current = moved;
+		}
+	}
+
+	fn generate_wilson<R, P>(&mut self, start: usize, rng: &mut R, peek: &mut P)
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		let mut in_maze = vec![false; self.shape.count()];
+		in_maze[start] = true;
+		let mut unvisited: Vec<_> = (0..self.shape.count()).filter(|c| *c != start).collect();
+		let mut path: Vec<usize> = Vec::new();
+		while !unvisited.is_empty() {
+			let mut current = unvisited[rng.gen_range(0, unvisited.len())];
+			path.push(current);
+			while !in_maze[current] {
+				let next = *self.shape.neighbours(current).choose(rng).unwrap();
+				// Walking into the path again erases the loop that was made.
+				if let Some(index) = path.iter().position(|p| *p == next) {
+					let erased: Vec<_> = path.drain(index..).collect();
+					for pair in erased.windows(2).rev() {
+						self.carve_wall(pair[0], pair[1], true, peek);
+					}
+					path.push(next);
+				} else {
+					self.carve_wall(current, next, false, peek);
+					path.push(next);
+				}
+				current = next;
+			}
+			for cell in path.drain(..) {
+				in_maze[cell] = true;
+				if let Some(index) = unvisited.iter().position(|u| *u == cell) {
+					unvisited.swap_remove(index);
+				}
+			}
+		}
+	}
+
+	fn generate_kruskal<R, P>(&mut self, rng: &mut R, peek: &mut P)
+	where
+		R: Rng + ?Sized,
+		P: FnMut(&Grid<S>, usize, usize),
+	{
+		fn root(parts: &mut [usize], cell: usize) -> usize {
+			let parent = parts[cell];
+			if parent == cell {
+				return cell;
+			}
+			let root = root(parts, parent);
+			parts[cell] = root;
+			root
+		}
+
+		let mut parts: Vec<_> = (0..self.shape.count()).collect();
+		for (a, b) in &self.open {
+			let (root_a, root_b) = (root(&mut parts, *a), root(&mut parts, *b));
+			parts[root_a] = root_b;
+		}
+		let mut walls: Vec<_> = (0..self.shape.count())
+			.flat_map(|cell| {
+				self.shape
+					.neighbours(cell)
+					.into_iter()
+					.filter(move |other| cell < *other)
+					.map(move |other| (cell, other))
+			})
+			.filter(|(a, b)| !self.is_open(*a, *b))
+			.collect();
+		walls.shuffle(rng);
+		for (a, b) in walls {
+			let (root_a, root_b) = (root(&mut parts, a), root(&mut parts, b));
+			if root_a != root_b {
+				parts[root_a] = root_b;
+				self.carve_wall(a, b, false, peek);
+			}
+		}
+	}
+
+	/// The number of steps from `from` to every cell, `None` for the ones that
+	/// can't be reached.
+	pub fn distances(&self, from: usize) -> Vec<Option<usize>> {
+		let mut distances = vec![None; self.shape.count()];
+		distances[from] = Some(0);
+		let mut to_visit = VecDeque::new();
+		to_visit.push_back(from);
+		while let Some(next) = to_visit.pop_front() {
+			let distance = distances[next].unwrap();
+			for moved in self.openings(next) {
+				if distances[moved].is_none() {
+					distances[moved] = Some(distance + 1);
+					to_visit.push_back(moved);
+				}
+			}
+		}
+		distances
+	}
+
+	/// The cells along the shortest path from `from` to `to`, including both.
+	pub fn solve(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+		let mut came_from = vec![None; self.shape.count()];
+		came_from[from] = Some(from);
+		let mut to_visit = VecDeque::new();
+		to_visit.push_back(from);
+		while let Some(next) = to_visit.pop_front() {
+			if next == to {
+				let mut path = vec![to];
+				while *path.last().unwrap() != from {
+					path.push(came_from[*path.last().unwrap()].unwrap());
+				}
+				path.reverse();
+				return Some(path);
+			}
+			for moved in self.openings(next) {
+				if came_from[moved].is_none() {
+					came_from[moved] = Some(next);
+					to_visit.push_back(moved);
+				}
+			}
+		}
+		None
+	}
+
+	/// The sides with a wall, on the border or between cells, once each. The
+	/// border is left open next to `start` for shapes with an entrance.
+	pub fn walls(&self, start: usize) -> Vec<Side> {
+		(0..self.shape.count())
+			.flat_map(|cell| {
+				self.shape
+					.sides(cell)
+					.into_iter()
+					.filter(move |(_, other)| match other {
+						Some(other) => cell < *other && !self.is_open(cell, *other),
+						None => !(S::ENTRANCE && cell == start),
+					})
+					.map(|(side, _)| side)
+			})
+			.collect()
+	}
+
+	/// Draws the maze the same way [`crate::svg::render`] draws square ones.
+	pub fn svg(&self, start: usize, goal: usize, options: &DrawOptions) -> String {
+		let (width, height) = self.shape.size();
+		let cell = options.cell_size;
+		// Other shapes than squares don't add up to whole pixels.
+		let round = |size: f64| (size * 100.0).round() / 100.0;
+		let (width, height) = (
+			round(width * cell + 2.0 * options.margin),
+			round(height * cell + 2.0 * options.margin),
+		);
+		let scale = |(x, y): (f64, f64)| (options.margin + x * cell, options.margin + y * cell);
+
+		let mut svg = String::new();
+		writeln!(
+			svg,
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+			width, height
+		)
+		.unwrap();
+		writeln!(
+			svg,
+			r#"<rect width="{}" height="{}" fill="{}"/>"#,
+			width, height, options.background
+		)
+		.unwrap();
+
+		if options.heatmap {
+			let distances = self.distances(start);
+			let max = distances.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+			writeln!(svg, r#"<g fill="{}">"#, options.heatmap_color).unwrap();
+			for (c, distance) in distances.iter().enumerate() {
+				if let Some(distance) = distance {
+					// Sides follow each other around the cell, so only the first
+					// one needs a move.
+					let sides = self.shape.sides(c);
+					let (x, y) = scale(sides[0].0.start());
+					writeln!(
+						svg,
+						r#"<path d="M{:.2} {:.2}{}Z" fill-opacity="{:.3}"/>"#,
+						x,
+						y,
+						sides.iter().map(|(side, _)| side.segment(&scale)).collect::<String>(),
+						*distance as f64 / max
+					)
+					.unwrap();
+				}
+			}
+			svg.push_str("</g>\n");
+		}
+
+		if options.solution {
+			if let Some(cells) = self.solve(start, goal) {
+				writeln!(
+					svg,
+					r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+					cells
+						.iter()
+						.map(|c| {
+							let (x, y) = scale(self.shape.center(*c));
+							format!("{:.2},{:.2}", x, y)
+						})
+						.collect::<Vec<_>>()
+						.join(" "),
+					options.solution_color,
+					cell / 4.0
+				)
+				.unwrap();
+			}
+		}
+
+		if options.markers {
+			for (c, color) in &[(start, &options.start_color), (goal, &options.goal_color)] {
+				let (x, y) = scale(self.shape.center(*c));
+				writeln!(
+					svg,
+					r#"<circle cx="{:.2}" cy="{:.2}" r="{}" fill="{}"/>"#,
+					x,
+					y,
+					cell * 0.3,
+					color
+				)
+				.unwrap();
+			}
+		}
+
+		writeln!(
+			svg,
+			r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+			self.walls(start)
+				.iter()
+				.map(|side| side.path(&scale))
+				.collect::<String>(),
+			options.wall_color,
+			options.wall_thickness
+		)
+		.unwrap();
+		svg.push_str("</svg>\n");
+		svg
+	}
+
+	/// Draws the maze the same way [`Grid::svg`] does.
+	pub fn rasterize(&self, start: usize, goal: usize, options: &DrawOptions) -> Result<Image, String> {
+		let (width, height) = self.shape.size();
+		let cell = options.cell_size;
+		let scale = |(x, y): (f64, f64)| (options.margin + x * cell, options.margin + y * cell);
+		let mut image = Image::new(
+			(width * cell + 2.0 * options.margin).round() as usize,
+			(height * cell + 2.0 * options.margin).round() as usize,
+			color_of(&options.background)?,
+		);
+
+		if options.heatmap {
+			let color = color_of(&options.heatmap_color)?;
+			let distances = self.distances(start);
+			let max = distances.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+			for (c, distance) in distances.iter().enumerate() {
+				if let Some(distance) = distance {
+					let outline: Vec<_> = self
+						.shape
+						.sides(c)
+						.iter()
+						.flat_map(|(side, _)| side.points())
+						.map(scale)
+						.collect();
+					image.blend_polygon(&outline, color, *distance as f64 / max);
+				}
+			}
+		}
+
+		if options.solution {
+			if let Some(cells) = self.solve(start, goal) {
+				let color = color_of(&options.solution_color)?;
+				for pair in cells.windows(2) {
+					let (x0, y0) = scale(self.shape.center(pair[0]));
+					let (x1, y1) = scale(self.shape.center(pair[1]));
+					image.stroke_line(x0, y0, x1, y1, cell / 4.0, color);
+				}
+			}
+		}
+
+		if options.markers {
+			for (c, color) in &[(start, &options.start_color), (goal, &options.goal_color)] {
+				let (x, y) = scale(self.shape.center(*c));
+				image.fill_circle(x, y, cell * 0.3, color_of(color)?);
+			}
+		}
+
+		let color = color_of(&options.wall_color)?;
+		for side in self.walls(start) {
+			for pair in side.points().windows(2) {
+				let ((x0, y0), (x1, y1)) = (scale(pair[0]), scale(pair[1]));
+				image.stroke_line(x0, y0, x1, y1, options.wall_thickness, color);
+			}
+		}
+
+		Ok(image)
+	}
+
+	/// A path of cells as arrows, like [`crate::map::Direction`]s are shown
+	/// for square grids.
+	pub fn arrows(&self, path: &[usize]) -> String {
+		path.windows(2).map(|pair| self.shape.arrow(pair[0], pair[1])).collect()
+	}
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use crate::grid::{Grid, Shape, Side};
+use crate::map::Position;
+
+/// Half the height of a hexagon that's one cell wide, from the middle to the
+/// top corner.
+const RADIUS: f64 = 0.577_350_269_189_625_8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HexDirection {
+	UpLeft,
+	UpRight,
+	Left,
+	Right,
+	DownLeft,
+	DownRight,
+}
+
+impl fmt::Display for HexDirection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				HexDirection::UpLeft => "↖",
+				HexDirection::UpRight => "↗",
+				HexDirection::Left => "←",
+				HexDirection::Right => "→",
+				HexDirection::DownLeft => "↙",
+				HexDirection::DownRight => "↘",
+			}
+		)
+	}
+}
+
+/// Going clockwise from the upper right side, the same order as the sides of
+/// a cell.
+pub const HEX_DIRECTIONS: [HexDirection; 6] = [
+	HexDirection::UpRight,
+	HexDirection::Right,
+	HexDirection::DownRight,
+	HexDirection::DownLeft,
+	HexDirection::Left,
+	HexDirection::UpLeft,
+];
+
+/// Rows of hexagons with a corner pointing up, where every other row is
+/// shifted half a cell to the right.
+pub struct Hex {
+	pub rows: usize,
+	pub columns: usize,
+}
+
+impl Hex {
+	pub fn position(&self, cell: usize) -> Position {
+		Position(cell / self.columns, cell % self.columns)
+	}
+
+	/// The neighbouring cell in the direction, if there's one.
+	pub fn move_in_direction(&self, current: &Position, dir: &HexDirection) -> Option<Position> {
+		let Position(r, c) = *current;
+		// Odd rows are shifted right, so their diagonal neighbours are one
+		// column further right than those of even rows.
+		let shift = r % 2;
+		let (r, c) = match dir {
+			HexDirection::UpLeft => (r.checked_sub(1)?, (c + shift).checked_sub(1)?),
+			HexDirection::UpRight => (r.checked_sub(1)?, c + shift),
+			HexDirection::Left => (r, c.checked_sub(1)?),
+			HexDirection::Right => (r, c + 1),
+			HexDirection::DownLeft => (r + 1, (c + shift).checked_sub(1)?),
+			HexDirection::DownRight => (r + 1, c + shift),
+		};
+		Some(Position(r, c)).filter(|p| p.0 < self.rows && p.1 < self.columns)
+	}
+
+	fn corners(&self, cell: usize) -> [(f64, f64); 6] {
+		let (x, y) = self.center(cell);
+		let (half, quarter) = (0.5, RADIUS / 2.0);
+		[
+			(x, y - RADIUS),
+			(x + half, y - quarter),
+			(x + half, y + quarter),
+			(x, y + RADIUS),
+			(x - half, y + quarter),
+			(x - half, y - quarter),
+		]
+	}
+}
+
+impl Shape for Hex {
+	fn count(&self) -> usize {
+		self.rows * self.columns
+	}
+
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)> {
+		let corners = self.corners(cell);
+		let pos = self.position(cell);
+		HEX_DIRECTIONS
+			.iter()
+			.enumerate()
+			.map(|(i, dir)| {
+				(
+					Side::Line(corners[i], corners[(i + 1) % 6]),
+					self.move_in_direction(&pos, dir).and_then(|p| self.cell(&p)),
+				)
+			})
+			.collect()
+	}
+
+	fn center(&self, cell: usize) -> (f64, f64) {
+		let Position(r, c) = self.position(cell);
+		(c as f64 + 0.5 + (r % 2) as f64 * 0.5, RADIUS + r as f64 * RADIUS * 1.5)
+	}
+
+	fn size(&self) -> (f64, f64) {
+		let shifted = if self.rows > 1 { 0.5 } else { 0.0 };
+		(
+			self.columns as f64 + shifted,
+			RADIUS * 2.0 + (self.rows - 1) as f64 * RADIUS * 1.5,
+		)
+	}
+
+	fn cell(&self, pos: &Position) -> Option<usize> {
+		Some(pos.0 * self.columns + pos.1).filter(|_| pos.0 < self.rows && pos.1 < self.columns)
+	}
+
+	fn arrow(&self, from: usize, to: usize) -> char {
+		let (from, to) = (self.position(from), self.position(to));
+		let dir = HEX_DIRECTIONS
+			.iter()
+			.find(|dir| self.move_in_direction(&from, dir) == Some(to))
+			.unwrap();
+		dir.to_string().chars().next().unwrap()
+	}
+}
+
+/// Draws the maze with characters, each cell four wide and two high, with
+/// `/`, `\` and `|` for the walls.
+pub fn text(grid: &Grid<Hex>) -> String {
+	let hex = &grid.shape;
+	let width = 4 * hex.columns + 1 + if hex.rows > 1 { 2 } else { 0 };
+	let mut lines = vec![vec![' '; width]; 2 * hex.rows + 1];
+	for cell in 0..hex.count() {
+		let pos = hex.position(cell);
+		let x = 4 * pos.1 + 2 * (pos.0 % 2);
+		let y = 2 * pos.0;
+		for dir in HEX_DIRECTIONS.iter() {
+			let wall = match hex.move_in_direction(&pos, dir).and_then(|p| hex.cell(&p)) {
+				Some(other) => !grid.is_open(cell, other),
+				None => true,
+			};
+			if wall {
+				let (dx, dy, c) = match dir {
+					HexDirection::UpLeft => (1, 0, '/'),
+					HexDirection::UpRight => (3, 0, '\\'),
+					HexDirection::Left => (0, 1, '|'),
+					HexDirection::Right => (4, 1, '|'),
+					HexDirection::DownLeft => (1, 2, '\\'),
+					HexDirection::DownRight => (3, 2, '/'),
+				};
+				lines[y + dy][x + dx] = c;
+			}
+		}
+	}
+	lines
+		.iter()
+		.map(|line| line.iter().collect::<String>().trim_end().to_string())
+		.collect::<Vec<_>>()
+		.join("\n")
+}
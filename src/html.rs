@@ -0,0 +1,144 @@
+use std::fmt::Write;
+
+use crate::draw::DrawOptions;
+use crate::map::{Direction, Map, Position};
+use crate::svg;
+
+/// Color of the player's marker.
+const PLAYER_COLOR: &str = "#f5a623";
+
+/// Moves the player with the arrow keys, through the cells open in each
+/// direction, and over to the other side through edges that wrap around.
+/// `maze` is defined before this.
+const SCRIPT: &str = r#"const player = document.getElementById("player");
+const solution = document.getElementById("solution");
+const time = document.getElementById("time");
+const moves = {ArrowUp: [-1, 0, 8], ArrowLeft: [0, -1, 4], ArrowRight: [0, 1, 2], ArrowDown: [1, 0, 1]};
+let position, started, timer, finished;
+
+function seconds() {
+	return ((performance.now() - started) / 1000).toFixed(1) + " s";
+}
+
+function restart() {
+	position = maze.start.slice();
+	started = null;
+	finished = false;
+	clearInterval(timer);
+	time.textContent = "0.0 s";
+	place();
+}
+
+function place() {
+	player.setAttribute("cx", maze.margin + (position[1] + 0.5) * maze.cell);
+	player.setAttribute("cy", maze.margin + (position[0] + 0.5) * maze.cell);
+}
+
+document.addEventListener("keydown", event => {
+	const move = moves[event.key];
+	if (!move) {
+		return;
+	}
+	event.preventDefault();
+	if (finished || !(maze.open[position[0] * maze.columns + position[1]] & move[2])) {
+		return;
+	}
+	if (started === null) {
+		started = performance.now();
+		timer = setInterval(() => time.textContent = seconds(), 100);
+	}
+	position = [(position[0] + move[0] + maze.rows) % maze.rows, (position[1] + move[1] + maze.columns) % maze.columns];
+	place();
+	if (position[0] === maze.goal[0] && position[1] === maze.goal[1]) {
+		finished = true;
+		clearInterval(timer);
+		time.textContent = "Solved in " + seconds();
+	}
+});
+
+// Buttons lose focus after a click, so that the arrow keys and space don't
+// press them again.
+document.getElementById("reveal").addEventListener("click", event => {
+	solution.style.visibility = "visible";
+	event.target.blur();
+});
+document.getElementById("restart").addEventListener("click", event => {
+	solution.style.visibility = "hidden";
+	restart();
+	event.target.blur();
+});
+restart();
+"#;
+
+/// A web page with the maze, that can be played with the arrow keys from the
+/// start to the goal. It times the run and can reveal the solution, and needs
+/// nothing but a browser.
+pub fn render(map: &Map, start: Position, goal: Position, options: &DrawOptions) -> String {
+	let cell = options.cell_size;
+	let mut html = String::from(
+		"<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Maze</title>\n<style>\n\
+		 body { font-family: sans-serif; text-align: center; }\n\
+		 #time { display: inline-block; min-width: 8em; font-variant-numeric: tabular-nums; }\n\
+		 </style>\n</head>\n<body>\n",
+	);
+	html.push_str(
+		"<p><span id=\"time\">0.0 s</span> <button id=\"reveal\">Reveal solution</button> \
+		 <button id=\"restart\">Restart</button></p>\n",
+	);
+
+	writeln!(
+		html,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+		options.width(map),
+		options.height(map)
+	)
+	.unwrap();
+	// The solution is drawn separately, hidden until it's asked for, and the
+	// player needs to see where they're going.
+	let layers = DrawOptions {
+		solution: false,
+		markers: true,
+		..options.clone()
+	};
+	svg::write_layers(map, start, goal, &layers, &mut html);
+	writeln!(
+		html,
+		r#"<path id="solution" d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round" style="visibility: hidden"/>"#,
+		svg::solution_path(map, start, goal, options).unwrap_or_default(),
+		options.solution_color,
+		cell / 4.0
+	)
+	.unwrap();
+	writeln!(
+		html,
+		r#"<circle id="player" r="{}" fill="{}" stroke="{}" stroke-width="{}"/>"#,
+		cell * 0.3,
+		PLAYER_COLOR,
+		options.wall_color,
+		cell / 20.0
+	)
+	.unwrap();
+	html.push_str("</svg>\n<p>Use the arrow keys to get from the start to the goal.</p>\n<script>\n");
+
+	let open: Vec<String> = (0..map.rows)
+		.flat_map(|r| (0..map.columns).map(move |c| Position(r, c)))
+		.map(|pos| map.openings(&pos).iter().map(Direction::bit).sum::<u8>().to_string())
+		.collect();
+	writeln!(
+		html,
+		"const maze = {{rows: {}, columns: {}, cell: {}, margin: {}, start: [{}, {}], goal: [{}, {}], open: [{}]}};",
+		map.rows,
+		map.columns,
+		cell,
+		options.margin,
+		start.0,
+		start.1,
+		goal.0,
+		goal.1,
+		open.join(",")
+	)
+	.unwrap();
+	html.push_str(SCRIPT);
+	html.push_str("</script>\n</body>\n</html>\n");
+	html
+}
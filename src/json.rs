@@ -0,0 +1,243 @@
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::map::{Algorithm, Direction, Map, Position, Wrap, DIRECTIONS};
+use crate::mask::Mask;
+
+/// How a [`Map`] is stored, every cell as the sum of the bits of the
+/// directions it's open in. Cells on edges that wrap around can be open
+/// across them, the cells a mask leaves out are closed on all sides.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Map")]
+struct Cells {
+	rows: usize,
+	columns: usize,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	wrap: Option<Wrap>,
+	/// The mask as its lines of text, see [`Mask::lines`].
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	mask: Option<Vec<String>>,
+	cells: Vec<Vec<u8>>,
+}
+
+impl From<&Map> for Cells {
+	fn from(map: &Map) -> Cells {
+		Cells {
+			rows: map.rows,
+			columns: map.columns,
+			wrap: Some(map.wrap()).filter(|wrap| *wrap != Wrap::None),
+			mask: map.mask().map(Mask::lines),
+			cells: (0..map.rows)
+				.map(|r| {
+					(0..map.columns)
+						.map(|c| map.openings(&Position(r, c)).iter().map(Direction::bit).sum())
+						.collect()
+				})
+				.collect(),
+		}
+	}
+}
+
+impl Cells {
+	fn into_map(self) -> Result<Map, String> {
+		if self.rows == 0 || self.columns == 0 {
+			return Err(String::from("A maze must have at least one row and one column"));
+		}
+		if self.cells.len() != self.rows || self.cells.iter().any(|row| row.len() != self.columns) {
+			return Err(format!("Expected {} rows of {} cells each", self.rows, self.columns));
+		}
+
+		let wrap = self.wrap.unwrap_or(Wrap::None);
+		let mut map = match &self.mask {
+			Some(_) if wrap != Wrap::None => return Err(String::from("A maze with a mask can't wrap around")),
+			Some(lines) => {
+				let mask = Mask::parse(lines.join("\n").as_bytes())?;
+				if (mask.rows, mask.columns) != (self.rows, self.columns) {
+					return Err(format!(
+						"The mask is {} by {} cells, but the maze is {} by {}",
+						mask.rows, mask.columns, self.rows, self.columns
+					));
+				}
+				Map::with_mask(mask)
+			}
+			None => Map::wrapping(self.rows, self.columns, wrap)?,
+		};
+		for (r, row) in self.cells.iter().enumerate() {
+			for (c, openings) in row.iter().enumerate() {
+				let pos = Position(r, c);
+				if *openings > 0b1111 {
+					return Err(format!("Cell ({}, {}) has unknown openings {}", r, c, openings));
+				}
+				if map.is_masked(&pos) {
+					if *openings != 0 {
+						return Err(format!("Cell ({}, {}) is left out by the mask, but open", r, c));
+					}
+					continue;
+				}
+				for dir in DIRECTIONS.iter() {
+					let open = openings & dir.bit() != 0;
+					match map.move_in_direction(&pos, dir) {
+						None if open => return Err(format!("Cell ({}, {}) is open {:?} out of the maze", r, c, dir)),
+						None => (),
+						Some(other) => {
+							if open != (self.cells[other.0][other.1] & dir.opposite().bit() != 0) {
+								return Err(format!(
+									"Cells ({}, {}) and ({}, {}) disagree on the wall between them",
+									r, c, other.0, other.1
+								));
+							}
+							map.set(&pos, dir, !open);
+						}
+					}
+				}
+			}
+		}
+		Ok(map)
+	}
+}
+
+impl Serialize for Map {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		Cells::from(self).serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Map {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Map, D::Error> {
+		Cells::deserialize(deserializer)?.into_map().map_err(de::Error::custom)
+	}
+}
+
+impl Serialize for Algorithm {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.name())
+	}
+}
+
+impl<'de> Deserialize<'de> for Algorithm {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Algorithm, D::Error> {
+		let name = String::deserialize(deserializer)?;
+		Algorithm::from_name(&name).ok_or_else(|| de::Error::custom(format!("Unknown algorithm \"{}\"", name)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use crate::document::Document;
+	use crate::map::ALGORITHMS;
+
+	#[test]
+	fn reads_back_the_json_of_generated_maps() {
+		let diamond = Mask::parse(b"..#..\n.###.\n#####\n.###.\n..#..\n").unwrap();
+		let blanks = [
+			Map::new(4, 6),
+			Map::wrapping(5, 3, Wrap::Horizontal).unwrap(),
+			Map::wrapping(4, 4, Wrap::Both).unwrap(),
+			Map::with_mask(diamond),
+		];
+		for blank in &blanks {
+			for algorithm in &ALGORITHMS {
+				for seed in 0..3 {
+					let (start, goal) = blank.ends();
+					let mut rng = StdRng::seed_from_u64(seed);
+					let map = Map::generate_in(*algorithm, blank.clone(), start, &mut rng, |_| {}, |_, _, _| {});
+					let json = Document {
+						map: map.clone(),
+						seed: Some(seed),
+						algorithm: Some(*algorithm),
+						start,
+						goal,
+					}
+					.to_json();
+					let document = Document::from_json(&json).unwrap_or_else(|e| panic!("{}\n{}", e, json));
+					assert!(document.map == map, "{}", json);
+					assert_eq!(document.seed, Some(seed));
+					assert_eq!(document.algorithm, Some(*algorithm));
+					assert_eq!((document.start, document.goal), (start, goal));
+				}
+			}
+		}
+	}
+
+	fn error_of(json: &str) -> String {
+		Document::from_json(json)
+			.err()
+			.expect("The JSON should have been rejected")
+	}
+
+	#[test]
+	fn rejects_inconsistent_mazes() {
+		let error = error_of(r#"{"rows":1,"columns":2,"cells":[[2,0]],"start":[0,0],"goal":[0,1]}"#);
+		assert!(
+			error.starts_with("Cells (0, 0) and (0, 1) disagree on the wall between them"),
+			"{}",
+			error
+		);
+
+		let error = error_of(r#"{"rows":1,"columns":2,"cells":[[10,4]],"start":[0,0],"goal":[0,1]}"#);
+		assert!(error.starts_with("Cell (0, 0) is open Up out of the maze"), "{}", error);
+
+		// Across the left and right edges, which only wrap around when asked to.
+		let error = error_of(r#"{"rows":1,"columns":3,"cells":[[6,6,6]],"start":[0,0],"goal":[0,2]}"#);
+		assert!(
+			error.starts_with("Cell (0, 0) is open Left out of the maze"),
+			"{}",
+			error
+		);
+		let wrapped = r#"{"rows":1,"columns":3,"wrap":"horizontal","cells":[[6,6,6]],"start":[0,0],"goal":[0,2]}"#;
+		assert!(Document::from_json(wrapped).is_ok());
+
+		let error = error_of(r#"{"rows":1,"columns":2,"cells":[[2,4]],"start":[0,0],"goal":[0,2]}"#);
+		assert_eq!(error, "The goal (0, 2) is outside the maze");
+		let error = error_of(r#"{"rows":1,"columns":2,"cells":[[2,4]],"start":[1,0],"goal":[0,1]}"#);
+		assert_eq!(error, "The start (1, 0) is outside the maze");
+	}
+
+	#[test]
+	fn rejects_masks_that_dont_fit_the_maze() {
+		// The lower left cell is left out of the maze.
+		let masked = |cells: &str, start: &str| {
+			format!(
+				r####"{{"rows":2,"columns":2,"mask":["##",".#"],"cells":{},"start":{},"goal":[1,1]}}"####,
+				cells, start
+			)
+		};
+		assert!(Document::from_json(&masked("[[2,5],[0,8]]", "[0,0]")).is_ok());
+
+		let error = error_of(&masked("[[2,5],[2,12]]", "[0,0]"));
+		assert!(
+			error.starts_with("Cell (1, 0) is left out by the mask, but open"),
+			"{}",
+			error
+		);
+		let error = error_of(&masked("[[2,5],[0,12]]", "[0,0]"));
+		assert!(
+			error.starts_with("Cell (1, 1) is open Left out of the maze"),
+			"{}",
+			error
+		);
+		assert_eq!(
+			error_of(&masked("[[2,5],[0,8]]", "[1,0]")),
+			"The start (1, 0) is outside the maze"
+		);
+
+		let error = error_of(
+			r####"{"rows":2,"columns":3,"mask":["##"],"cells":[[0,0,0],[0,0,0]],"start":[0,0],"goal":[0,1]}"####,
+		);
+		assert!(
+			error.starts_with("The mask is 1 by 2 cells, but the maze is 2 by 3"),
+			"{}",
+			error
+		);
+		let error = error_of(
+			r####"{"rows":1,"columns":3,"wrap":"horizontal","mask":["###"],"cells":[[0,0,0]],"start":[0,0],"goal":[0,2]}"####,
+		);
+		assert!(error.starts_with("A maze with a mask can't wrap around"), "{}", error);
+	}
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+
+use crate::ascii;
+use crate::grid::{Grid, Shape, Side};
+use crate::map::{Direction, Map, Position};
+use crate::mesh::{self, Mesh, MeshOptions};
+
+/// A direction in a maze with levels, either along a level or up or down the
+/// stairs.
+///
+/// The stairs aren't part of [`Direction`], nor the level of [`Position`],
+/// as flat square maps use those everywhere: their walls, the bits of a cell
+/// in every export format, and the turns of the solvers all rely on there
+/// being exactly four directions. Levels are a [`Shape`] like the other grids
+/// instead, with directions of their own as hexagons have.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LevelDirection {
+	Along(Direction),
+	Ascend,
+	Descend,
+}
+
+impl fmt::Display for LevelDirection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LevelDirection::Along(dir) => write!(f, "{}", dir),
+			LevelDirection::Ascend => write!(f, "⇑"),
+			LevelDirection::Descend => write!(f, "⇓"),
+		}
+	}
+}
+
+/// The directions on a level, in the order of the sides of a cell, and then
+/// the stairs.
+const LEVEL_DIRECTIONS: [LevelDirection; 6] = [
+	LevelDirection::Along(Direction::Up),
+	LevelDirection::Along(Direction::Right),
+	LevelDirection::Along(Direction::Down),
+	LevelDirection::Along(Direction::Left),
+	LevelDirection::Ascend,
+	LevelDirection::Descend,
+];
+
+/// Square grids stacked on top of each other, where stairs lead between cells
+/// in the same place on neighbouring levels. Cells are numbered level by level
+/// from the bottom up, and row by row on each level. Drawn flat, the levels
+/// are side by side with a cell between them.
+pub struct Levels {
+	pub levels: usize,
+	pub rows: usize,
+	pub columns: usize,
+}
+
+impl Levels {
+	/// The level of the cell, and where it is on that level.
+	pub fn position(&self, cell: usize) -> (usize, Position) {
+		let on_level = cell % (self.rows * self.columns);
+		(
+			cell / (self.rows * self.columns),
+			Position(on_level / self.columns, on_level % self.columns),
+		)
+	}
+
+	fn cell_at(&self, level: usize, pos: &Position) -> Option<usize> {
+		Some((level * self.rows + pos.0) * self.columns + pos.1)
+			.filter(|_| level < self.levels && pos.0 < self.rows && pos.1 < self.columns)
+	}
+
+	/// The neighbouring cell in the direction, if there's one.
+	pub fn move_in_direction(&self, cell: usize, dir: &LevelDirection) -> Option<usize> {
+		let (level, Position(r, c)) = self.position(cell);
+		let (level, r, c) = match dir {
+			LevelDirection::Along(Direction::Up) => (level, r.checked_sub(1)?, c),
+			LevelDirection::Along(Direction::Left) => (level, r, c.checked_sub(1)?),
+			LevelDirection::Along(Direction::Right) => (level, r, c + 1),
+			LevelDirection::Along(Direction::Down) => (level, r + 1, c),
+			LevelDirection::Ascend => (level + 1, r, c),
+			LevelDirection::Descend => (level.checked_sub(1)?, r, c),
+		};
+		self.cell_at(level, &Position(r, c))
+	}
+
+	/// The x coordinate of the left side of the level, drawn flat.
+	fn left(&self, level: usize) -> f64 {
+		(level * (self.columns + 1)) as f64
+	}
+}
+
+impl Shape for Levels {
+	fn count(&self) -> usize {
+		self.levels * self.rows * self.columns
+	}
+
+	/// Only the sides on the level, the stairs go through the floor.
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)> {
+		let (level, Position(r, c)) = self.position(cell);
+		let (x, y) = (self.left(level) + c as f64, r as f64);
+		let corners = [(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)];
+		LEVEL_DIRECTIONS[..4]
+			.iter()
+			.enumerate()
+			.map(|(i, dir)| {
+				(
+					Side::Line(corners[i], corners[(i + 1) % 4]),
+					self.move_in_direction(cell, dir),
+				)
+			})
+			.collect()
+	}
+
+	fn neighbours(&self, cell: usize) -> Vec<usize> {
+		LEVEL_DIRECTIONS
+			.iter()
+			.filter_map(|dir| self.move_in_direction(cell, dir))
+			.collect()
+	}
+
+	fn center(&self, cell: usize) -> (f64, f64) {
+		let (level, Position(r, c)) = self.position(cell);
+		(self.left(level) + c as f64 + 0.5, r as f64 + 0.5)
+	}
+
+	fn size(&self) -> (f64, f64) {
+		(self.left(self.levels) - 1.0, self.rows as f64)
+	}
+
+	/// The cell at a row and a column of the bottom level.
+	fn cell(&self, pos: &Position) -> Option<usize> {
+		self.cell_at(0, pos)
+	}
+
+	fn arrow(&self, from: usize, to: usize) -> char {
+		let dir = LEVEL_DIRECTIONS
+			.iter()
+			.find(|dir| self.move_in_direction(from, dir) == Some(to))
+			.unwrap();
+		dir.to_string().chars().next().unwrap()
+	}
+}
+
+/// The walls of one level, as a map of its own.
+fn floor(grid: &Grid<Levels>, level: usize) -> Map {
+	let levels = &grid.shape;
+	let mut map = Map::new(levels.rows, levels.columns);
+	for r in 0..levels.rows {
+		for c in 0..levels.columns {
+			let pos = Position(r, c);
+			let cell = levels.cell_at(level, &pos).unwrap();
+			for dir in &[Direction::Right, Direction::Down] {
+				if let Some(other) = levels.move_in_direction(cell, &LevelDirection::Along(*dir)) {
+					if grid.is_open(cell, other) {
+						map.set(&pos, dir, false);
+					}
+				}
+			}
+		}
+	}
+	map
+}
+
+/// The cells of the level with stairs in the direction open.
+fn stairs(grid: &Grid<Levels>, level: usize, dir: &LevelDirection) -> Vec<Position> {
+	let levels = &grid.shape;
+	(0..levels.rows * levels.columns)
+		.map(|i| Position(i / levels.columns, i % levels.columns))
+		.filter(|pos| {
+			let cell = levels.cell_at(level, pos).unwrap();
+			levels
+				.move_in_direction(cell, dir)
+				.is_some_and(|other| grid.is_open(cell, other))
+		})
+		.collect()
+}
+
+/// Draws the levels side by side from the bottom up, each like
+/// [`ascii::render`], with `<` in cells with stairs up and `>` in cells with
+/// stairs down.
+pub fn text(grid: &Grid<Levels>) -> String {
+	let levels = &grid.shape;
+	let width = (3 * levels.columns + 1).max(format!("Level {}", levels.levels).len());
+	let mut lines = vec![String::new(); 2 * levels.rows + 2];
+	for level in 0..levels.levels {
+		let mut floor: Vec<Vec<char>> = ascii::render(&floor(grid, level))
+			.lines()
+			.map(|line| line.chars().collect())
+			.collect();
+		for (dir, dx, marker) in &[(LevelDirection::Ascend, 1, '<'), (LevelDirection::Descend, 2, '>')] {
+			for pos in stairs(grid, level, dir) {
+				floor[2 * pos.0 + 1][3 * pos.1 + dx] = *marker;
+			}
+		}
+
+		let separator = if level == 0 { "" } else { "  " };
+		lines[0].push_str(&format!(
+			"{}{:<width$}",
+			separator,
+			format!("Level {}", level + 1),
+			width = width
+		));
+		for (line, chars) in lines[1..].iter_mut().zip(floor) {
+			let chars: String = chars.into_iter().collect();
+			line.push_str(&format!("{}{:<width$}", separator, chars, width = width));
+		}
+	}
+	lines.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n")
+}
+
+/// Builds the levels as solids stacked on top of each other, see
+/// [`mesh::build_levels`].
+pub fn mesh(grid: &Grid<Levels>, options: &MeshOptions) -> Mesh {
+	let levels = grid.shape.levels;
+	let floors: Vec<_> = (0..levels).map(|level| floor(grid, level)).collect();
+	let stairwells: Vec<_> = (0..levels)
+		.map(|level| stairs(grid, level, &LevelDirection::Descend))
+		.collect();
+	mesh::build_levels(&floors, &stairwells, options)
+}
@@ -1,15 +1,95 @@
 use crossterm::{cursor, ExecutableCommand, QueueableCommand};
+use std::cell::RefCell;
+use std::fs;
 use std::io::{stdout, Write};
+use std::path::Path;
 
-use clap::{App, Arg, ArgGroup, ArgMatches};
+use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::str::FromStr;
 
 use std::{thread, time::Duration};
 
+mod ascii;
+
+mod code;
+
+mod compare;
+
+mod delta;
+use delta::Delta;
+
+mod document;
+use document::Document;
+
+mod draw;
+use draw::DrawOptions;
+
+mod gif;
+use gif::GifEncoder;
+
+mod graph;
+
+mod grid;
+use grid::{Grid, Shape};
+
+mod hex;
+use hex::Hex;
+
+mod html;
+
+#[cfg(feature = "serde")]
+mod json;
+
+mod levels;
+use levels::Levels;
+
 mod map;
+use map::Algorithm;
 use map::Direction;
 use map::Map;
 use map::Position;
+use map::Wrap;
+
+mod mask;
+use mask::Mask;
+
+mod mesh;
+use mesh::MeshOptions;
+
+mod pdf;
+
+mod png;
+
+mod polar;
+use polar::Polar;
+
+mod raster;
+
+mod schem;
+use schem::SchematicOptions;
+
+mod sheet;
+use sheet::{Paper, SheetOptions};
+
+mod solvers;
+use solvers::Strategy;
+
+mod square;
+
+mod stats;
+
+mod svg;
+
+mod tikz;
+use tikz::Labels;
+
+mod tiled;
+
+mod uniformity;
+
+mod validate;
 
 fn main() {
 	let matches = App::new("Maze Generator")
@@ -29,13 +109,46 @@ fn main() {
 				.help("Number of columns of the generated map")
 				.display_order(1),
 		)
+		.arg(
+			Arg::with_name("MASK")
+				.long("mask")
+				.takes_value(true)
+				.conflicts_with_all(&["ROWS", "COLUMNS", "INPUT", "IMPORT_CODE", "EXPORT_CODE"])
+				.help("Generate a maze in the shape of a PBM or PGM image, or of text drawn with # and .")
+				.display_order(2),
+		)
+		.arg(
+			Arg::with_name("WRAP")
+				.long("wrap")
+				.takes_value(true)
+				.possible_values(&["none", "horizontal", "both"])
+				.conflicts_with_all(&["MASK", "INPUT", "IMPORT_CODE", "EXPORT_CODE"])
+				.help("Wrap the left and right edges around, or both them and the top and bottom edges [default: none]")
+				.display_order(3),
+		)
+		.arg(
+			Arg::with_name("GRID")
+				.long("grid")
+				.takes_value(true)
+				.possible_values(&["square", "hex", "polar", "delta"])
+				.help("The shape of the cells of the generated map [default: square]")
+				.display_order(4),
+		)
+		.arg(
+			Arg::with_name("LEVELS")
+				.long("levels")
+				.takes_value(true)
+				.validator(check_arg_is_number)
+				.help("Stacks this many levels of square grids, connected by stairs")
+				.display_order(5),
+		)
 		.arg(
 			Arg::with_name("START_ROW")
 				.long("start_row")
 				.default_value("0")
 				.validator(check_arg_is_number)
 				.help("The row to start generating from")
-				.display_order(2),
+				.display_order(6),
 		)
 		.arg(
 			Arg::with_name("START_COLUMN")
@@ -43,7 +156,7 @@ fn main() {
 				.default_value("0")
 				.validator(check_arg_is_number)
 				.help("The column to start generating from")
-				.display_order(3),
+				.display_order(7),
 		)
 		.arg(
 			Arg::with_name("DELAY")
@@ -51,54 +164,535 @@ fn main() {
 				.default_value("50")
 				.validator(check_arg_is_number)
 				.help("The ms delay between steps")
-				.display_order(4),
+				.display_order(8),
+		)
+		.arg(
+			Arg::with_name("SEED")
+				.long("seed")
+				.takes_value(true)
+				.validator(check_arg_is_number)
+				.help("The seed for the random number generator [default: random]")
+				.display_order(9),
 		)
 		.arg(
 			Arg::with_name("DFS")
 				.long("dfs")
 				.help("Use the depth first search algorithm for maze generation [default]")
-				.display_order(5),
+				.display_order(10),
 		)
 		.arg(
 			Arg::with_name("TREE")
 				.long("tree")
 				.help("Use the binary tree maze algorithm for maze generation")
-				.display_order(6),
+				.display_order(11),
 		)
 		.arg(
 			Arg::with_name("PRIM")
 				.long("prim")
 				.help("Use Prim's algorithm for maze generation")
-				.display_order(7),
+				.display_order(12),
 		)
 		.arg(
 			Arg::with_name("AB")
 				.long("ab")
 				.help("Use the Aldous-Broder algorithm for maze generation")
-				.display_order(8),
+				.display_order(13),
 		)
 		.arg(
 			Arg::with_name("DIV")
 				.long("div")
 				.help("Use the recursive division method for maze generation")
-				.display_order(9),
+				.display_order(14),
 		)
 		.arg(
 			Arg::with_name("WILSON")
 				.long("wilson")
 				.help("Use Wilson's algorithm (loop-erased random walk) for maze generation")
-				.display_order(9),
+				.display_order(15),
+		)
+		.arg(
+			Arg::with_name("KRUSKAL")
+				.long("kruskal")
+				.help("Use Kruskal's algorithm for maze generation")
+				.display_order(16),
+		)
+		.arg(
+			Arg::with_name("SOLVER")
+				.long("solver")
+				.takes_value(true)
+				.possible_values(&["left", "right", "pledge", "tremaux", "fill", "all"])
+				.help("Also solve the maze with a wall follower, Pledge, Trémaux or dead-end filling")
+				.display_order(17),
+		)
+		.arg(
+			Arg::with_name("STATS")
+				.long("stats")
+				.help("Print statistics about the generated maze")
+				.display_order(18),
+		)
+		.arg(
+			Arg::with_name("JSON")
+				.long("json")
+				.requires("STATS")
+				.help("Print the statistics as JSON")
+				.display_order(19),
+		)
+		.arg(
+			Arg::with_name("INPUT")
+				.long("input")
+				.takes_value(true)
+				.conflicts_with_all(&["ALGORITHM", "ANIMATE_TO"])
+				.help("Read a maze written with --output instead of generating one")
+				.display_order(20),
+		)
+		.arg(
+			Arg::with_name("IMPORT_CODE")
+				.long("import-code")
+				.takes_value(true)
+				.conflicts_with_all(&["ALGORITHM", "ANIMATE_TO", "INPUT"])
+				.help("Read a maze from a code printed by --export-code instead of generating one"),
+		)
+		.arg(
+			Arg::with_name("EXPORT_CODE")
+				.long("export-code")
+				.help("Print a short code that the maze can be shared and read back with"),
+		)
+		.arg(
+			Arg::with_name("OUTPUT")
+				.long("output")
+				.takes_value(true)
+				.help("Write the maze to a file instead of the terminal, the format is guessed from the extension")
+				.display_order(21),
+		)
+		.arg(
+			Arg::with_name("FORMAT")
+				.long("format")
+				.takes_value(true)
+				.possible_values(FORMATS)
+				.help("The format of the written maze [default: text]")
+				.display_order(22),
+		)
+		.arg(
+			Arg::with_name("CELL_SIZE")
+				.long("cell-size")
+				.default_value("20")
+				.validator(check_arg_is_decimal)
+				.help("Width and height of a cell when drawing the maze, or in mm for 3D models")
+				.display_order(23),
+		)
+		.arg(
+			Arg::with_name("WALL")
+				.long("wall")
+				.default_value("2")
+				.validator(check_arg_is_decimal)
+				.help("Thickness of the walls when drawing the maze, or in mm for 3D models")
+				.display_order(24),
+		)
+		.arg(
+			Arg::with_name("MARGIN")
+				.long("margin")
+				.default_value("10")
+				.validator(check_arg_is_decimal)
+				.help("Empty space around the drawn maze")
+				.display_order(25),
+		)
+		.arg(
+			Arg::with_name("WALL_COLOR")
+				.long("wall-color")
+				.default_value("black")
+				.validator(check_arg_is_color)
+				.help("Color of the walls when drawing the maze")
+				.display_order(26),
+		)
+		.arg(
+			Arg::with_name("BACKGROUND")
+				.long("background")
+				.default_value("white")
+				.validator(check_arg_is_color)
+				.help("Color of the background when drawing the maze")
+				.display_order(27),
+		)
+		.arg(
+			Arg::with_name("SOLUTION_COLOR")
+				.long("solution-color")
+				.default_value("red")
+				.validator(check_arg_is_color)
+				.help("Color of the solution when drawing the maze")
+				.display_order(28),
+		)
+		.arg(
+			Arg::with_name("START_COLOR")
+				.long("start-color")
+				.default_value("green")
+				.validator(check_arg_is_color)
+				.help("Color of the start marker when drawing the maze")
+				.display_order(29),
+		)
+		.arg(
+			Arg::with_name("GOAL_COLOR")
+				.long("goal-color")
+				.default_value("blue")
+				.validator(check_arg_is_color)
+				.help("Color of the goal marker when drawing the maze")
+				.display_order(30),
+		)
+		.arg(
+			Arg::with_name("HEATMAP_COLOR")
+				.long("heatmap-color")
+				.default_value("orange")
+				.validator(check_arg_is_color)
+				.help("Color of the distance heatmap when drawing the maze")
+				.display_order(31),
+		)
+		.arg(
+			Arg::with_name("WALL_HEIGHT")
+				.long("wall-height")
+				.default_value("10")
+				.validator(check_arg_is_decimal)
+				.help("Height of the walls in mm when building a 3D model of the maze"),
+		)
+		.arg(
+			Arg::with_name("BASE_THICKNESS")
+				.long("base-thickness")
+				.default_value("2")
+				.validator(check_arg_is_decimal)
+				.help("Thickness of the base plate in mm when building a 3D model of the maze"),
+		)
+		.arg(
+			Arg::with_name("GROOVE")
+				.long("groove")
+				.help("Engrave the solution into the base plate of the 3D model"),
+		)
+		.arg(
+			Arg::with_name("GROOVE_DEPTH")
+				.long("groove-depth")
+				.default_value("1")
+				.validator(check_arg_is_decimal)
+				.help("Depth in mm of the engraved solution"),
+		)
+		.arg(
+			Arg::with_name("WALL_BLOCK")
+				.long("wall-block")
+				.default_value("minecraft:stone_bricks")
+				.help("The block walls are built of in schematics"),
+		)
+		.arg(
+			Arg::with_name("FLOOR_BLOCK")
+				.long("floor-block")
+				.default_value("minecraft:smooth_stone")
+				.help("The block the floor is built of in schematics"),
+		)
+		.arg(
+			Arg::with_name("BLOCK_HEIGHT")
+				.long("block-height")
+				.default_value("3")
+				.validator(check_arg_is_number)
+				.help("Height of the walls in blocks in schematics"),
+		)
+		.arg(
+			Arg::with_name("OPENINGS")
+				.long("openings")
+				.help("Leave an entrance and an exit in the outer wall of schematics, next to the start and goal"),
+		)
+		.arg(
+			Arg::with_name("ANIMATE_TO")
+				.long("animate-to")
+				.takes_value(true)
+				.help("Record the generation as an animated GIF instead of showing it in the terminal"),
+		)
+		.arg(
+			Arg::with_name("FRAME_DELAY")
+				.long("frame-delay")
+				.default_value("50")
+				.validator(check_arg_is_number)
+				.help("The ms delay between frames of the animation"),
+		)
+		.arg(
+			Arg::with_name("STEPS_PER_FRAME")
+				.long("steps-per-frame")
+				.default_value("1")
+				.validator(check_arg_is_number)
+				.help("Number of generation steps in each frame of the animation"),
+		)
+		.arg(
+			Arg::with_name("SOLUTION")
+				.long("solution")
+				.help("Draw the solution")
+				.display_order(32),
+		)
+		.arg(
+			Arg::with_name("MARKERS")
+				.long("markers")
+				.help("Draw markers on the start and goal")
+				.display_order(33),
+		)
+		.arg(
+			Arg::with_name("HEATMAP")
+				.long("heatmap")
+				.help("Shade the cells by their distance from the start")
+				.display_order(34),
+		)
+		.arg(
+			Arg::with_name("LABELS")
+				.long("labels")
+				.takes_value(true)
+				.possible_values(&["position", "distance"])
+				.help("Label the cells of TikZ pictures with their position or distance from the start")
+				.display_order(35),
+		)
+		.group(ArgGroup::with_name("ALGORITHM").args(&["DFS", "TREE", "PRIM", "AB", "DIV", "WILSON", "KRUSKAL"]))
+		.subcommand(
+			SubCommand::with_name("sheet")
+				.about("Lays out mazes on printable PDF pages, followed by an answer key")
+				.arg(
+					Arg::with_name("OUTPUT")
+						.long("output")
+						.takes_value(true)
+						.required(true)
+						.help("The PDF file to write"),
+				)
+				.arg(
+					Arg::with_name("MAZES")
+						.long("mazes")
+						.default_value("4")
+						.validator(check_arg_is_positive)
+						.help("Number of mazes to generate"),
+				)
+				.arg(
+					Arg::with_name("PER_PAGE")
+						.long("per-page")
+						.default_value("4")
+						.validator(check_arg_is_per_page)
+						.help("Number of mazes on each page, at most 36"),
+				)
+				.arg(
+					Arg::with_name("PAPER")
+						.long("paper")
+						.default_value("a4")
+						.possible_values(&["a4", "letter"])
+						.help("The paper size of the pages"),
+				)
+				.arg(
+					Arg::with_name("TITLE")
+						.long("title")
+						.default_value("Mazes")
+						.help("The title at the top of every page"),
+				)
+				.arg(
+					Arg::with_name("ROWS")
+						.long("rows")
+						.default_value("10")
+						.validator(check_arg_is_positive)
+						.help("Number of rows of the generated maps"),
+				)
+				.arg(
+					Arg::with_name("COLUMNS")
+						.long("columns")
+						.default_value("10")
+						.validator(check_arg_is_positive)
+						.help("Number of columns of the generated maps"),
+				)
+				.arg(
+					Arg::with_name("ALGORITHM")
+						.long("algorithm")
+						.default_value("dfs")
+						.possible_values(&["dfs", "tree", "prim", "ab", "div", "wilson", "kruskal"])
+						.help("The algorithm to generate the mazes with"),
+				)
+				.arg(
+					Arg::with_name("SEED")
+						.long("seed")
+						.takes_value(true)
+						.validator(check_arg_is_number)
+						.help("The seed of the first maze, the following mazes count up from it [default: random]"),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("compare")
+				.about("Compares the generation algorithms over many seeded mazes")
+				.arg(
+					Arg::with_name("SAMPLES")
+						.long("samples")
+						.default_value("100")
+						.validator(check_arg_is_number)
+						.help("Number of mazes to generate with each algorithm"),
+				)
+				.arg(
+					Arg::with_name("ROWS")
+						.long("rows")
+						.default_value("30")
+						.validator(check_arg_is_positive)
+						.help("Number of rows of the generated maps"),
+				)
+				.arg(
+					Arg::with_name("COLUMNS")
+						.long("columns")
+						.default_value("30")
+						.validator(check_arg_is_positive)
+						.help("Number of columns of the generated maps"),
+				)
+				.arg(
+					Arg::with_name("SEED")
+						.long("seed")
+						.default_value("0")
+						.validator(check_arg_is_number)
+						.help("The seed of the first sample, the following samples count up from it"),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("uniformity")
+				.about("Tests whether the algorithms pick every spanning tree of a small grid equally often")
+				.arg(
+					Arg::with_name("SAMPLES")
+						.long("samples")
+						.default_value("19200")
+						.validator(check_arg_is_number)
+						.help("Number of mazes to generate with each algorithm"),
+				)
+				.arg(
+					Arg::with_name("ROWS")
+						.long("rows")
+						.default_value("3")
+						.validator(check_arg_is_number)
+						.help("Number of rows of the grid"),
+				)
+				.arg(
+					Arg::with_name("COLUMNS")
+						.long("columns")
+						.default_value("3")
+						.validator(check_arg_is_number)
+						.help("Number of columns of the grid"),
+				)
+				.arg(
+					Arg::with_name("SEED")
+						.long("seed")
+						.default_value("0")
+						.validator(check_arg_is_number)
+						.help("The seed of the first sample, the following samples count up from it"),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("validate")
+				.about(
+					"Checks that every algorithm generates perfect mazes for many sizes and seeds, that can be read back from their text",
+				)
+				.arg(
+					Arg::with_name("SAMPLES")
+						.long("samples")
+						.default_value("20")
+						.validator(check_arg_is_number)
+						.help("Number of mazes to generate with each algorithm for each size"),
+				)
+				.arg(
+					Arg::with_name("MAX_SIZE")
+						.long("max-size")
+						.default_value("10")
+						.validator(check_arg_is_number)
+						.help("The largest number of rows and columns to try"),
+				)
+				.arg(
+					Arg::with_name("SEED")
+						.long("seed")
+						.default_value("0")
+						.validator(check_arg_is_number)
+						.help("The seed of the first sample, the following samples count up from it"),
+				),
 		)
-		.group(ArgGroup::with_name("ALGORITHM").args(&[
-			"DFS",
-			"TREE",
-			"PRIM",
-			"AB",
-			"DIV",
-			"WILSON",
-		]))
 		.get_matches();
 
+	if let Some(matches) = matches.subcommand_matches("sheet") {
+		let rows = get_arg_as_t(matches, "ROWS");
+		let columns = get_arg_as_t(matches, "COLUMNS");
+		let algorithm = Algorithm::from_name(matches.value_of("ALGORITHM").unwrap()).unwrap();
+		let seed: u64 = if matches.is_present("SEED") {
+			get_arg_as_t(matches, "SEED")
+		} else {
+			rand::random()
+		};
+		let mazes: Vec<_> = (0..get_arg_as_t::<u64>(matches, "MAZES"))
+			.map(|i| {
+				let seed = seed.wrapping_add(i);
+				let mut rng = StdRng::seed_from_u64(seed);
+				let map = Map::generate(algorithm, rows, columns, Position(0, 0), &mut rng, |_| {}, |_, _, _| {});
+				Document {
+					start: Position(0, 0),
+					goal: Position(map.rows - 1, map.columns - 1),
+					map,
+					seed: Some(seed),
+					algorithm: Some(algorithm),
+				}
+			})
+			.collect();
+		let options = SheetOptions {
+			title: get_arg_as_t(matches, "TITLE"),
+			paper: Paper::from_name(matches.value_of("PAPER").unwrap()).unwrap(),
+			per_page: get_arg_as_t(matches, "PER_PAGE"),
+		};
+		fs::write(matches.value_of("OUTPUT").unwrap(), sheet::render(&mazes, &options))
+			.expect("Could not write output.");
+		return;
+	}
+	if let Some(matches) = matches.subcommand_matches("validate") {
+		let (checked, failures) = validate::check_generators(
+			get_arg_as_t(matches, "MAX_SIZE"),
+			get_arg_as_t(matches, "SAMPLES"),
+			get_arg_as_t(matches, "SEED"),
+		);
+		for failure in &failures {
+			let maze = format!(
+				"{} {}×{} with seed {}",
+				failure.algorithm.name(),
+				failure.rows,
+				failure.columns,
+				failure.seed
+			);
+			if !failure.report.is_perfect() {
+				println!("{} is not perfect:\n{}\n", maze, failure.report);
+			}
+			if !failure.round_trips {
+				println!("{} is not read back the same from its text\n", maze);
+			}
+		}
+		println!(
+			"{} of {} mazes were perfect and read back from their text",
+			checked - failures.len(),
+			checked
+		);
+		if !failures.is_empty() {
+			std::process::exit(1);
+		}
+		return;
+	}
+	if let Some(matches) = matches.subcommand_matches("uniformity") {
+		let report = uniformity::test(
+			get_arg_as_t(matches, "ROWS"),
+			get_arg_as_t(matches, "COLUMNS"),
+			get_arg_as_t(matches, "SAMPLES"),
+			get_arg_as_t(matches, "SEED"),
+		)
+		.unwrap_or_else(|e| {
+			eprintln!("{}", e);
+			std::process::exit(1);
+		});
+		println!("{}", report);
+		if !report.passed() {
+			std::process::exit(1);
+		}
+		return;
+	}
+	if let Some(matches) = matches.subcommand_matches("compare") {
+		println!(
+			"{}",
+			compare::compare(
+				get_arg_as_t(matches, "ROWS"),
+				get_arg_as_t(matches, "COLUMNS"),
+				get_arg_as_t(matches, "SAMPLES"),
+				get_arg_as_t(matches, "SEED"),
+			)
+		);
+		return;
+	}
+
 	let rows = get_arg_as_t(&matches, "ROWS");
 	let columns = get_arg_as_t(&matches, "COLUMNS");
 	let start_pos = Position(
@@ -106,11 +700,123 @@ fn main() {
 		get_arg_as_t(&matches, "START_COLUMN"),
 	);
 	let delay = get_arg_as_t(&matches, "DELAY");
+	let seed = if matches.is_present("SEED") {
+		get_arg_as_t(&matches, "SEED")
+	} else {
+		rand::random()
+	};
+	let mut rng = StdRng::seed_from_u64(seed);
+	let algorithm = if matches.is_present("TREE") {
+		Algorithm::Tree
+	} else if matches.is_present("PRIM") {
+		Algorithm::Prim
+	} else if matches.is_present("AB") {
+		Algorithm::AldousBroder
+	} else if matches.is_present("DIV") {
+		Algorithm::Division
+	} else if matches.is_present("WILSON") {
+		Algorithm::Wilson
+	} else if matches.is_present("KRUSKAL") {
+		Algorithm::Kruskal
+	} else {
+		Algorithm::Dfs
+	};
+	match (matches.value_of("GRID"), matches.value_of("LEVELS")) {
+		(Some("hex"), None) => {
+			let hex = Hex { rows, columns };
+			let text: GridFormat<Hex> = |grid, _| format!("{}\n", hex::text(grid)).into_bytes();
+			return generate_grid(hex, algorithm, start_pos, &mut rng, &matches, &[("text", text)]);
+		}
+		(Some("polar"), None) => {
+			if matches.occurrences_of("COLUMNS") > 0 {
+				eprintln!("A circular maze has no columns, its number of rings is set with --rows");
+				std::process::exit(1);
+			}
+			let polar = Polar::new(rows).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			});
+			return generate_grid(polar, algorithm, start_pos, &mut rng, &matches, &[]);
+		}
+		(Some("delta"), None) => {
+			let delta = Delta::new(rows, columns).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			});
+			return generate_grid(delta, algorithm, start_pos, &mut rng, &matches, &[]);
+		}
+		(Some("square"), Some(_)) | (None, Some(_)) => {
+			let levels = get_arg_as_t(&matches, "LEVELS");
+			if levels == 0 {
+				eprintln!("A maze needs at least one level");
+				std::process::exit(1);
+			}
+			let levels = Levels { levels, rows, columns };
+			let text: GridFormat<Levels> = |grid, _| format!("{}\n", levels::text(grid)).into_bytes();
+			let stl: GridFormat<Levels> = |grid, matches| levels::mesh(grid, &mesh_options(matches)).to_stl();
+			let obj: GridFormat<Levels> =
+				|grid, matches| levels::mesh(grid, &mesh_options(matches)).to_obj().into_bytes();
+			return generate_grid(
+				levels,
+				algorithm,
+				start_pos,
+				&mut rng,
+				&matches,
+				&[("text", text), ("stl", stl), ("obj", obj)],
+			);
+		}
+		(Some(_), Some(_)) => {
+			eprintln!("--levels only works on square grids");
+			std::process::exit(1);
+		}
+		_ => {}
+	}
+
+	let blank = match matches.value_of("MASK") {
+		Some(path) => {
+			let mask = Mask::parse(&fs::read(path).expect("Could not read mask.")).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			});
+			Map::with_mask(mask)
+		}
+		None => {
+			let wrap = matches.value_of("WRAP").and_then(Wrap::from_name).unwrap_or(Wrap::None);
+			Map::wrapping(rows, columns, wrap).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			})
+		}
+	};
+	// Masks usually leave out the corner, so generation starts from the first
+	// cell of the maze unless told otherwise.
+	let start_pos = if matches.occurrences_of("START_ROW") == 0 && matches.occurrences_of("START_COLUMN") == 0 {
+		blank.ends().0
+	} else if start_pos.0 >= blank.rows || start_pos.1 >= blank.columns || blank.is_masked(&start_pos) {
+		eprintln!("The generation must start from a cell of the maze");
+		std::process::exit(1);
+	} else {
+		start_pos
+	};
+
+	let output = matches.value_of("OUTPUT");
+	let format = output_format(&matches);
+	// The text formats that can be read back don't keep the mask, so the maze
+	// would lose its shape.
+	if matches.is_present("MASK") && output.is_some() && ["text", "ascii"].contains(&format) {
+		eprintln!("A maze with a mask can't be written as {}", format);
+		std::process::exit(1);
+	}
+	let animate = format == "text"
+		&& output.is_none()
+		&& !matches.is_present("ANIMATE_TO")
+		&& !matches.is_present("INPUT")
+		&& !matches.is_present("IMPORT_CODE");
 
 	let mut stdout = stdout();
-	let did_hide = stdout.execute(cursor::Hide).is_ok();
+	let did_hide = animate && stdout.execute(cursor::Hide).is_ok();
 	let initial_peek_fn = |map: &Map| println!("{}", map);
-	let peek_fn = |map: &Map, pos: &Position, dir: &Direction| {
+	let mut draw_wall = |map: &Map, pos: &Position, dir: &Direction| {
 		let chars = map.get_chars(pos, dir);
 		let rows = (map.rows - pos.0) as u16 + if dir == &Direction::Up { 1 } else { 0 };
 		let columns = pos.1 as u16 + if dir == &Direction::Right { 1 } else { 0 };
@@ -152,30 +858,114 @@ fn main() {
 			}
 		}
 		stdout.flush().expect("Could not flush.");
+	};
+	let peek_fn = |map: &Map, pos: &Position, dir: &Direction| {
+		draw_wall(map, pos, dir);
+		// Walls across edges that wrap around are drawn on both edges.
+		if let Some((pos, dir)) = map.across_edge(pos, dir) {
+			draw_wall(map, &pos, &dir);
+		}
 
 		if delay > 0 {
 			thread::sleep(Duration::from_millis(delay));
 		}
 	};
-	let map = if matches.is_present("TREE") {
-		Map::generate_tree(rows, columns, initial_peek_fn, peek_fn)
-	} else if matches.is_present("PRIM") {
-		Map::generate_prim(rows, columns, start_pos, initial_peek_fn, peek_fn)
-	} else if matches.is_present("AB") {
-		Map::generate_ab(rows, columns, start_pos, initial_peek_fn, peek_fn)
-	} else if matches.is_present("DIV") {
-		Map::generate_div(rows, columns, initial_peek_fn, peek_fn)
-	} else if matches.is_present("WILSON") {
-		Map::generate_wilson(rows, columns, start_pos, initial_peek_fn, peek_fn)
+	let document = if let Some(path) = matches.value_of("INPUT") {
+		read_document(path)
+	} else if let Some(code) = matches.value_of("IMPORT_CODE") {
+		let map = Map::from_code(code).unwrap_or_else(|e| {
+			eprintln!("{}", e);
+			std::process::exit(1);
+		});
+		let (start, goal) = map.ends();
+		Document {
+			start,
+			goal,
+			map,
+			seed: None,
+			algorithm: None,
+		}
 	} else {
-		Map::generate_dfs(rows, columns, start_pos, initial_peek_fn, peek_fn)
+		let map = if animate {
+			Map::generate_in(algorithm, blank, start_pos, &mut rng, initial_peek_fn, peek_fn)
+		} else if let Some(path) = matches.value_of("ANIMATE_TO") {
+			generate_animation(algorithm, blank, start_pos, &mut rng, &matches, path)
+		} else {
+			Map::generate_in(algorithm, blank, start_pos, &mut rng, |_| {}, |_, _, _| {})
+		};
+		let (start, goal) = map.ends();
+		Document {
+			start,
+			goal,
+			map,
+			seed: Some(seed),
+			algorithm: Some(algorithm),
+		}
 	};
+	let map = &document.map;
+	let (start, goal) = (document.start, document.goal);
 
 	if did_hide {
 		stdout.execute(cursor::Show).expect("Could not show cursor.");
 	}
 
-	if let Some(path) = map.solve(Position(0, 0), Position(map.rows - 1, map.columns - 1)) {
+	let contents = match format {
+		#[cfg(feature = "serde")]
+		"json" => Some(format!("{}\n", document.to_json()).into_bytes()),
+		"ascii" => Some(ascii::render(map).into_bytes()),
+		"dot" => Some(graph::dot(map, start, goal).into_bytes()),
+		"graphml" => Some(graph::graphml(map, start, goal).into_bytes()),
+		"edges" => Some(graph::edge_list(map).into_bytes()),
+		"stl" => Some(mesh::build(map, start, goal, &mesh_options(&matches)).to_stl()),
+		"obj" => Some(
+			mesh::build(map, start, goal, &mesh_options(&matches))
+				.to_obj()
+				.into_bytes(),
+		),
+		"schem" => Some(
+			schem::render(map, start, goal, &schematic_options(&matches)).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			}),
+		),
+		"html" => Some(html::render(map, start, goal, &draw_options(&matches)).into_bytes()),
+		"tmx" | "tmj" => Some(tilemap(map, start, goal, &matches, format).into_bytes()),
+		"tikz" => Some(
+			tikz::render(
+				map,
+				start,
+				goal,
+				&draw_options(&matches),
+				matches.value_of("LABELS").and_then(Labels::from_name),
+			)
+			.unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			})
+			.into_bytes(),
+		),
+		"svg" => Some(svg::render(map, start, goal, &draw_options(&matches)).into_bytes()),
+		"png" => Some(png::encode(&rasterize(map, start, goal, &draw_options(&matches)))),
+		_ if output.is_some() => Some(format!("{}\n", map).into_bytes()),
+		// Mazes that weren't drawn while they were generated are printed as
+		// they are.
+		_ if !animate => {
+			println!("{}", map);
+			None
+		}
+		_ => None,
+	};
+	if let Some(contents) = &contents {
+		match output {
+			Some(path) => fs::write(path, contents).expect("Could not write output."),
+			None => {
+				stdout.write_all(contents).expect("Could not write.");
+				return;
+			}
+		}
+	}
+
+	if let Some(path) = map.solve(start, goal) {
 		println!(
 			"Path: {}",
 			path.into_iter().map(|d| format!("{}", d)).collect::<String>()
@@ -183,6 +973,336 @@ fn main() {
 	} else {
 		println!("No path through maze");
 	}
+
+	if matches.is_present("EXPORT_CODE") {
+		println!("Code: {}", map.to_code());
+	}
+
+	if matches.is_present("STATS") {
+		let stats = map.stats();
+		if matches.is_present("JSON") {
+			println!("{}", stats.to_json());
+		} else {
+			println!("{}", stats);
+		}
+	}
+
+	if let Some(name) = matches.value_of("SOLVER") {
+		let strategies = match Strategy::from_name(name) {
+			Some(strategy) => vec![strategy],
+			None => solvers::STRATEGIES.to_vec(),
+		};
+		for strategy in strategies {
+			solve_with(map, strategy, delay);
+		}
+	}
+}
+
+const FORMATS: &[&str] = &[
+	"text",
+	"svg",
+	"png",
+	"html",
+	"tikz",
+	"ascii",
+	"dot",
+	"graphml",
+	"edges",
+	"stl",
+	"obj",
+	"schem",
+	"tmx",
+	"tmj",
+	#[cfg(feature = "serde")]
+	"json",
+];
+
+fn output_format<'a>(matches: &'a ArgMatches) -> &'a str {
+	matches
+		.value_of("FORMAT")
+		.or_else(|| {
+			matches
+				.value_of("OUTPUT")
+				.and_then(|o| Path::new(o).extension())
+				.and_then(|e| e.to_str())
+				.map(|e| if e == "tex" { "tikz" } else { e })
+				.filter(|e| FORMATS.contains(e))
+		})
+		.unwrap_or("text")
+}
+
+/// Reads a maze, the format is guessed from the extension.
+fn read_document(path: &str) -> Document {
+	let document = match Path::new(path).extension().and_then(|e| e.to_str()) {
+		#[cfg(feature = "serde")]
+		Some("json") => Document::from_json(&fs::read_to_string(path).expect("Could not read input.")),
+		_ => {
+			let text = fs::read_to_string(path).expect("Could not read input.");
+			if text.trim_start().starts_with('+') {
+				ascii::parse(&text)
+			} else {
+				Map::from_text(&text).map(|map| Document {
+					start: Position(0, 0),
+					goal: Position(map.rows - 1, map.columns - 1),
+					map,
+					seed: None,
+					algorithm: None,
+				})
+			}
+		}
+	};
+	document.unwrap_or_else(|e| {
+		eprintln!("{}: {}", path, e);
+		std::process::exit(1);
+	})
+}
+
+fn draw_options(matches: &ArgMatches) -> DrawOptions {
+	DrawOptions {
+		cell_size: get_arg_as_t(matches, "CELL_SIZE"),
+		wall_thickness: get_arg_as_t(matches, "WALL"),
+		margin: get_arg_as_t(matches, "MARGIN"),
+		wall_color: get_arg_as_t(matches, "WALL_COLOR"),
+		background: get_arg_as_t(matches, "BACKGROUND"),
+		solution_color: get_arg_as_t(matches, "SOLUTION_COLOR"),
+		start_color: get_arg_as_t(matches, "START_COLOR"),
+		goal_color: get_arg_as_t(matches, "GOAL_COLOR"),
+		heatmap_color: get_arg_as_t(matches, "HEATMAP_COLOR"),
+		solution: matches.is_present("SOLUTION"),
+		markers: matches.is_present("MARKERS"),
+		heatmap: matches.is_present("HEATMAP"),
+	}
+}
+
+/// Sizes shared with drawing are taken as mm.
+fn mesh_options(matches: &ArgMatches) -> MeshOptions {
+	let options = MeshOptions {
+		cell_size: get_arg_as_t(matches, "CELL_SIZE"),
+		wall_thickness: get_arg_as_t(matches, "WALL"),
+		wall_height: get_arg_as_t(matches, "WALL_HEIGHT"),
+		base_thickness: get_arg_as_t(matches, "BASE_THICKNESS"),
+		groove: matches.is_present("GROOVE"),
+		groove_depth: get_arg_as_t(matches, "GROOVE_DEPTH"),
+	};
+	if options.groove && options.groove_depth >= options.base_thickness {
+		eprintln!("The groove must be shallower than the base plate is thick");
+		std::process::exit(1);
+	}
+	options
+}
+
+/// A Tiled map, with its tileset image written next to it.
+fn tilemap(map: &Map, start: Position, goal: Position, matches: &ArgMatches, format: &str) -> String {
+	let output = matches.value_of("OUTPUT").unwrap_or_else(|| {
+		eprintln!("Tiled maps are written together with their tileset, so they need --output");
+		std::process::exit(1);
+	});
+	let options = draw_options(matches);
+	let image = tiled::tileset(&options).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		std::process::exit(1);
+	});
+	let stem = Path::new(output).file_stem().and_then(|s| s.to_str()).unwrap_or("maze");
+	let name = format!("{}-tiles.png", stem);
+	fs::write(Path::new(output).with_file_name(&name), png::encode(&image)).expect("Could not write output.");
+	if format == "tmx" {
+		tiled::tmx(map, start, goal, &options, &name)
+	} else {
+		tiled::tmj(map, start, goal, &options, &name)
+	}
+}
+
+fn schematic_options(matches: &ArgMatches) -> SchematicOptions {
+	SchematicOptions {
+		wall_block: String::from(matches.value_of("WALL_BLOCK").unwrap()),
+		floor_block: String::from(matches.value_of("FLOOR_BLOCK").unwrap()),
+		height: get_arg_as_t(matches, "BLOCK_HEIGHT"),
+		openings: matches.is_present("OPENINGS"),
+	}
+}
+
+fn rasterize(map: &Map, start: Position, goal: Position, options: &DrawOptions) -> raster::Image {
+	raster::rasterize(map, start, goal, options).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		std::process::exit(1);
+	})
+}
+
+/// Generates the map while recording every step as a frame of an animated GIF,
+/// which is written to `path`.
+fn generate_animation(
+	algorithm: Algorithm,
+	blank: Map,
+	start_pos: Position,
+	rng: &mut StdRng,
+	matches: &ArgMatches,
+	path: &str,
+) -> Map {
+	let (start, goal) = blank.ends();
+	let frame_options = DrawOptions {
+		solution: false,
+		heatmap: false,
+		..draw_options(matches)
+	};
+	let delay = (get_arg_as_t::<u64>(matches, "FRAME_DELAY") / 10) as u16;
+	let steps_per_frame = get_arg_as_t::<usize>(matches, "STEPS_PER_FRAME").max(1);
+
+	let encoder: RefCell<Option<GifEncoder>> = RefCell::new(None);
+	let record = |map: &Map, delay: u16| {
+		let image = rasterize(map, start, goal, &frame_options);
+		encoder
+			.borrow_mut()
+			.get_or_insert_with(|| GifEncoder::new(image.width, image.height))
+			.add_frame(&image, delay);
+	};
+	let mut steps = 0;
+	let map = Map::generate_in(
+		algorithm,
+		blank,
+		start_pos,
+		rng,
+		|map| record(map, delay),
+		|map, _, _| {
+			steps += 1;
+			if steps % steps_per_frame == 0 {
+				record(map, delay);
+			}
+		},
+	);
+
+	// Lingers on the finished maze, drawn with everything asked for.
+	let mut encoder = encoder.into_inner().unwrap();
+	encoder.add_frame(&rasterize(&map, start, goal, &draw_options(matches)), 200);
+	fs::write(path, encoder.finish()).expect("Could not write animation.");
+
+	map
+}
+
+/// Writes a grid in a format that only some shapes have.
+type GridFormat<S> = fn(&Grid<S>, &ArgMatches) -> Vec<u8>;
+
+/// Generates a maze on a grid of another shape than squares, or on several
+/// levels, and writes it as SVG, PNG or one of the shape's own `formats`.
+fn generate_grid<S: Shape>(
+	shape: S,
+	algorithm: Algorithm,
+	start_pos: Position,
+	rng: &mut StdRng,
+	matches: &ArgMatches,
+	formats: &[(&str, GridFormat<S>)],
+) {
+	for (name, flag) in &[
+		("MASK", "--mask"),
+		("INPUT", "--input"),
+		("IMPORT_CODE", "--import-code"),
+		("EXPORT_CODE", "--export-code"),
+		("ANIMATE_TO", "--animate-to"),
+		("SOLVER", "--solver"),
+		("STATS", "--stats"),
+		("GROOVE", "--groove"),
+		("WRAP", "--wrap"),
+	] {
+		if matches.is_present(name) {
+			eprintln!("{} only works on square grids", flag);
+			std::process::exit(1);
+		}
+	}
+	if shape.count() == 0 {
+		eprintln!("A maze must have at least one row and one column");
+		std::process::exit(1);
+	}
+	let start = if matches.occurrences_of("START_ROW") == 0 && matches.occurrences_of("START_COLUMN") == 0 {
+		shape.ends().0
+	} else {
+		shape.cell(&start_pos).unwrap_or_else(|| {
+			eprintln!("The generation must start from a cell of the maze");
+			std::process::exit(1);
+		})
+	};
+	let grid = Grid::generate(algorithm, shape, start, rng).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		std::process::exit(1);
+	});
+	let (start, goal) = grid.shape.ends();
+
+	// Grids that can't be written as text are printed as SVG instead.
+	let format = match output_format(matches) {
+		"text"
+			if !matches.is_present("FORMAT")
+				&& !matches.is_present("OUTPUT")
+				&& !formats.iter().any(|(name, _)| *name == "text") =>
+		{
+			"svg"
+		}
+		format => format,
+	};
+	let contents = match format {
+		"svg" => grid.svg(start, goal, &draw_options(matches)).into_bytes(),
+		"png" => png::encode(
+			&grid.rasterize(start, goal, &draw_options(matches)).unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			}),
+		),
+		_ => match formats.iter().find(|(name, _)| *name == format) {
+			Some((_, write)) => write(&grid, matches),
+			None => {
+				let names: Vec<_> = formats.iter().map(|(name, _)| *name).chain(vec!["svg"]).collect();
+				eprintln!(
+					"Only the {} and png formats can be written for this grid",
+					names.join(", ")
+				);
+				std::process::exit(1);
+			}
+		},
+	};
+	match matches.value_of("OUTPUT") {
+		Some(path) => fs::write(path, contents).expect("Could not write output."),
+		None => {
+			stdout().write_all(&contents).expect("Could not write.");
+			if format != "text" {
+				return;
+			}
+		}
+	}
+
+	match grid.solve(start, goal) {
+		Some(path) => println!("Path: {}", grid.arrows(&path)),
+		None => println!("No path through maze"),
+	}
+}
+
+fn solve_with(map: &Map, strategy: Strategy, delay: u64) {
+	let (from, to) = map.ends();
+	let mut agent = strategy.agent(map, from, to);
+
+	let mut stdout = stdout();
+	let did_hide = stdout.execute(cursor::Hide).is_ok();
+	let height = 2 * map.rows as u16 + 1;
+	let mut first = true;
+	let outcome = solvers::run(map, agent.as_mut(), |map, agent| {
+		if !first {
+			stdout.queue(cursor::MoveUp(height)).expect("Could not move cursor.");
+		}
+		first = false;
+		stdout
+			.write_fmt(format_args!("{}", solvers::frame(map, |p| agent.mark(p))))
+			.expect("Could not write.");
+		stdout.flush().expect("Could not flush.");
+
+		if delay > 0 {
+			thread::sleep(Duration::from_millis(delay));
+		}
+	});
+	if did_hide {
+		stdout.execute(cursor::Show).expect("Could not show cursor.");
+	}
+
+	if outcome.solved {
+		println!("{}: solved in {} steps", strategy, outcome.steps);
+	} else {
+		println!("{}: failed after {} steps", strategy, outcome.steps);
+	}
 }
 
 fn check_arg_is_number(s: String) -> Result<(), String> {
@@ -193,6 +1313,31 @@ fn check_arg_is_number(s: String) -> Result<(), String> {
 	}
 }
 
+fn check_arg_is_positive(s: String) -> Result<(), String> {
+	match usize::from_str(&s) {
+		Ok(v) if v > 0 => Ok(()),
+		_ => Err("Must be a number larger than 0".to_string()),
+	}
+}
+
+fn check_arg_is_per_page(s: String) -> Result<(), String> {
+	match usize::from_str(&s) {
+		Ok(v) if v > 0 && v <= sheet::MAX_PER_PAGE => Ok(()),
+		_ => Err(format!("Must be a number from 1 to {}", sheet::MAX_PER_PAGE)),
+	}
+}
+
+fn check_arg_is_color(s: String) -> Result<(), String> {
+	raster::color_of(&s).map(|_| ())
+}
+
+fn check_arg_is_decimal(s: String) -> Result<(), String> {
+	match f64::from_str(&s) {
+		Ok(v) if v >= 0.0 && v.is_finite() => Ok(()),
+		_ => Err("Must be a decimal number".to_string()),
+	}
+}
+
 fn get_arg_as_t<T: FromStr>(matches: &ArgMatches, name: &str) -> T {
 	if let Some(s) = matches.value_of(name) {
 		if let Ok(v) = T::from_str(s) {
@@ -1,17 +1,21 @@
-use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::collections::{hash_map::Entry, HashMap};
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
+
+use crate::grid::{Grid, Shape};
+use crate::mask::Mask;
+use crate::square::Square;
 
 const UP: usize = 0b1000;
 const LEFT: usize = 0b0100;
 const RIGHT: usize = 0b0010;
 const DOWN: usize = 0b0001;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub struct WallJunction(usize);
 
 impl WallJunction {
@@ -50,11 +54,11 @@ impl WallJunction {
 	pub fn is_down(&self) -> bool {
 		self.is(DOWN)
 	}
-}
 
-impl Default for WallJunction {
-	fn default() -> Self {
-		WallJunction(0)
+	/// The directions walls lead out of the junction as a number from 0 to
+	/// 15, up is 8, left 4, right 2 and down 1.
+	pub fn bits(&self) -> usize {
+		self.0
 	}
 }
 
@@ -81,13 +85,26 @@ impl From<WallJunction> for char {
 	}
 }
 
+impl TryFrom<char> for WallJunction {
+	type Error = char;
+
+	fn try_from(c: char) -> Result<Self, Self::Error> {
+		(0..16).map(WallJunction).find(|wj| char::from(*wj) == c).ok_or(c)
+	}
+}
+
 impl fmt::Display for WallJunction {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "{}", char::from(*self))
 	}
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
 pub enum Direction {
 	Up,
 	Left,
@@ -110,16 +127,42 @@ impl fmt::Display for Direction {
 	}
 }
 
-const UPPER_LEFT: WallJunction = WallJunction(RIGHT | DOWN);
-const UPPER_RIGHT: WallJunction = WallJunction(LEFT | DOWN);
-const LOWER_LEFT: WallJunction = WallJunction(RIGHT | UP);
-const LOWER_RIGHT: WallJunction = WallJunction(LEFT | UP);
-const HORIZONTAL: WallJunction = WallJunction(LEFT | RIGHT);
-const VERTICAL: WallJunction = WallJunction(UP | DOWN);
+impl Direction {
+	pub fn opposite(&self) -> Direction {
+		match self {
+			Direction::Up => Direction::Down,
+			Direction::Left => Direction::Right,
+			Direction::Right => Direction::Left,
+			Direction::Down => Direction::Up,
+		}
+	}
+	pub fn turn_left(&self) -> Direction {
+		match self {
+			Direction::Up => Direction::Left,
+			Direction::Left => Direction::Down,
+			Direction::Right => Direction::Up,
+			Direction::Down => Direction::Right,
+		}
+	}
+	pub fn turn_right(&self) -> Direction {
+		self.turn_left().opposite()
+	}
+	/// The bit of the direction in a cell's openings, the same bits that
+	/// [`WallJunction`] uses for its walls.
+	pub fn bit(&self) -> u8 {
+		(match self {
+			Direction::Up => UP,
+			Direction::Left => LEFT,
+			Direction::Right => RIGHT,
+			Direction::Down => DOWN,
+		}) as u8
+	}
+}
 
-const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Left, Direction::Right, Direction::Down];
+pub const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Left, Direction::Right, Direction::Down];
 
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position(
 	/// Row
 	pub usize,
@@ -127,165 +170,290 @@ pub struct Position(
 	pub usize,
 );
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+	Dfs,
+	Tree,
+	Prim,
+	AldousBroder,
+	Division,
+	Wilson,
+	Kruskal,
+}
+
+pub const ALGORITHMS: [Algorithm; 7] = [
+	Algorithm::Dfs,
+	Algorithm::Tree,
+	Algorithm::Prim,
+	Algorithm::AldousBroder,
+	Algorithm::Division,
+	Algorithm::Wilson,
+	Algorithm::Kruskal,
+];
+
+impl Algorithm {
+	/// The short name, same as the command line flag.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Algorithm::Dfs => "dfs",
+			Algorithm::Tree => "tree",
+			Algorithm::Prim => "prim",
+			Algorithm::AldousBroder => "ab",
+			Algorithm::Division => "div",
+			Algorithm::Wilson => "wilson",
+			Algorithm::Kruskal => "kruskal",
+		}
+	}
+	pub fn from_name(name: &str) -> Option<Algorithm> {
+		ALGORITHMS.iter().copied().find(|a| a.name() == name)
+	}
+}
+
+impl fmt::Display for Algorithm {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Algorithm::Dfs => "Randomized depth first search",
+				Algorithm::Tree => "Random binary tree",
+				Algorithm::Prim => "Randomized Prim's algorithm",
+				Algorithm::AldousBroder => "Aldous-Broder algorithm",
+				Algorithm::Division => "Recursive division method",
+				Algorithm::Wilson => "Wilson's algorithm",
+				Algorithm::Kruskal => "Randomized Kruskal's algorithm",
+			}
+		)
+	}
+}
+
+/// Which edges of a map wrap around to the opposite edge, so that passages
+/// can cross them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
+pub enum Wrap {
+	None,
+	/// The left and right edges, like on a cylinder.
+	Horizontal,
+	/// The left and right, and the top and bottom edges, like on a torus.
+	Both,
+}
+
+impl Wrap {
+	pub fn from_name(name: &str) -> Option<Wrap> {
+		match name {
+			"none" => Some(Wrap::None),
+			"horizontal" => Some(Wrap::Horizontal),
+			"both" => Some(Wrap::Both),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Eq)]
 pub struct Map {
 	pub rows: usize,
 	pub columns: usize,
+	/// The walls below every cell but the last row, then those to the right of
+	/// every cell but the last column, and then the walls across the edges
+	/// that wrap around, right of the last column and below the last row.
 	map: Box<[bool]>,
+	/// The cells left out of the maze, if it isn't a full rectangle. They're
+	/// closed off on all sides.
+	mask: Option<Mask>,
+	wrap: Wrap,
 }
 
 impl Map {
-	fn new_with_value(rows: usize, columns: usize, value: bool) -> Map {
+	pub fn new(rows: usize, columns: usize) -> Map {
 		Map {
 			rows,
 			columns,
-			map: vec![value; rows * 2 * columns - (rows + columns)].into_boxed_slice(),
+			map: vec![true; (rows * 2 * columns).saturating_sub(rows + columns)].into_boxed_slice(),
+			mask: None,
+			wrap: Wrap::None,
 		}
 	}
-	pub fn new(rows: usize, columns: usize) -> Map {
-		Map::new_with_value(rows, columns, true)
+	/// A map with edges that wrap around, with all walls closed. It takes three
+	/// cells across a wrapped edge, or the cells on either side would already
+	/// be neighbours.
+	pub fn wrapping(rows: usize, columns: usize, wrap: Wrap) -> Result<Map, String> {
+		let (horizontal, vertical) = match wrap {
+			Wrap::None => (0, 0),
+			Wrap::Horizontal => (rows, 0),
+			Wrap::Both => (rows, columns),
+		};
+		if rows == 0 || columns == 0 {
+			return Err(String::from("A maze must have at least one row and one column"));
+		}
+		if horizontal > 0 && columns < 3 {
+			return Err(String::from(
+				"Wrapping the left and right edges needs at least 3 columns",
+			));
+		}
+		if vertical > 0 && rows < 3 {
+			return Err(String::from("Wrapping the top and bottom edges needs at least 3 rows"));
+		}
+		Ok(Map {
+			map: vec![true; rows * 2 * columns - (rows + columns) + horizontal + vertical].into_boxed_slice(),
+			wrap,
+			..Map::new(rows, columns)
+		})
+	}
+	/// A map in the shape of the mask, with all walls closed.
+	pub fn with_mask(mask: Mask) -> Map {
+		Map {
+			mask: Some(mask.clone()),
+			..Map::new(mask.rows, mask.columns)
+		}
+	}
+
+	#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+	pub fn wrap(&self) -> Wrap {
+		self.wrap
 	}
-	pub fn new_empty(rows: usize, columns: usize) -> Map {
-		Map::new_with_value(rows, columns, false)
+	#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+	pub fn mask(&self) -> Option<&Mask> {
+		self.mask.as_ref()
+	}
+	fn wraps_horizontally(&self) -> bool {
+		self.wrap != Wrap::None
+	}
+	fn wraps_vertically(&self) -> bool {
+		self.wrap == Wrap::Both
 	}
 
-	pub fn generate_dfs<F, G>(rows: usize, columns: usize, start: Position, mut initial_peek: F, mut peek: G) -> Map
+	pub fn is_masked(&self, pos: &Position) -> bool {
+		self.mask.as_ref().is_some_and(|m| m.is_excluded(pos))
+	}
+
+	/// The cells that are part of the maze, row by row.
+	pub fn cells(&self) -> impl Iterator<Item = Position> + '_ {
+		(0..self.rows)
+			.flat_map(move |r| (0..self.columns).map(move |c| Position(r, c)))
+			.filter(move |p| !self.is_masked(p))
+	}
+
+	/// The first and the last cell, the default start and goal.
+	pub fn ends(&self) -> (Position, Position) {
+		let mut cells = self.cells();
+		let first = cells.next().unwrap();
+		(first, cells.last().unwrap_or(first))
+	}
+
+	pub fn generate<R, F, G>(
+		algorithm: Algorithm,
+		rows: usize,
+		columns: usize,
+		start: Position,
+		rng: &mut R,
+		initial_peek: F,
+		peek: G,
+	) -> Map
 	where
+		R: Rng + ?Sized,
 		F: FnMut(&Map),
 		G: FnMut(&Map, &Position, &Direction),
 	{
-		let mut map = Map::new(rows, columns);
-		initial_peek(&map);
-		let mut rng = thread_rng();
-
-		let mut visited = HashSet::new();
-		visited.insert(start);
-		let mut to_visit = vec![start];
-
-		let mut moved_positions = Vec::with_capacity(4);
-
-		while let Some(next) = to_visit.pop() {
-			moved_positions.clear();
-			moved_positions.extend(
-				DIRECTIONS
-					.iter()
-					.filter_map(|d| map.move_in_direction(&next, d).map(|m| (m, d)))
-					.filter(|(m, _)| !visited.contains(&m)),
-			);
-			if moved_positions.len() > 1 {
-				to_visit.push(next);
-			}
-			if let Some((moved, dir)) = moved_positions.choose(&mut rng) {
-				map.set(&next, dir, false);
-				to_visit.push(*moved);
-				visited.insert(*moved);
-				peek(&map, &next, &dir);
-			}
-		}
-
-		map
+		Map::generate_in(algorithm, Map::new(rows, columns), start, rng, initial_peek, peek)
 	}
 
-	pub fn generate_tree<F, G>(rows: usize, columns: usize, mut initial_peek: F, mut peek: G) -> Map
+	/// Generates a maze in `map`, which has all its walls closed, like a map
+	/// from [`Map::with_mask`] for a maze in the shape of the mask.
+	pub fn generate_in<R, F, G>(
+		algorithm: Algorithm,
+		map: Map,
+		start: Position,
+		rng: &mut R,
+		initial_peek: F,
+		peek: G,
+	) -> Map
 	where
+		R: Rng + ?Sized,
 		F: FnMut(&Map),
 		G: FnMut(&Map, &Position, &Direction),
 	{
-		let mut map = Map::new(rows, columns);
-		initial_peek(&map);
-
-		for c in 1..map.columns {
-			map.set_left(&Position(0, c), false);
-
-			peek(&map, &Position(0, c), &Direction::Left);
-		}
-		for r in 1..map.rows {
-			map.set_above(&Position(r, 0), false);
-
-			peek(&map, &Position(r, 0), &Direction::Up);
-		}
-
-		for r in 1..map.rows {
-			for c in 1..map.columns {
-				if rand::random() {
-					map.set_left(&Position(r, c), false);
-					peek(&map, &Position(r, c), &Direction::Left);
-				} else {
-					map.set_above(&Position(r, c), false);
-					peek(&map, &Position(r, c), &Direction::Up);
-				}
-			}
+		match algorithm {
+			Algorithm::Tree => Map::generate_tree(map, rng, initial_peek, peek),
+			Algorithm::Division => Map::generate_div(map, rng, initial_peek, peek),
+			_ => Map::generate_on_grid(algorithm, map, start, rng, initial_peek, peek),
 		}
-
-		map
 	}
 
-	pub fn generate_prim<F, G>(rows: usize, columns: usize, start: Position, mut initial_peek: F, mut peek: G) -> Map
+	/// Generates the maze with the generators shared with the other grids,
+	/// see [`Grid::carve`].
+	fn generate_on_grid<R, F, G>(
+		algorithm: Algorithm,
+		mut map: Map,
+		start: Position,
+		rng: &mut R,
+		mut initial_peek: F,
+		mut peek: G,
+	) -> Map
 	where
+		R: Rng + ?Sized,
 		F: FnMut(&Map),
 		G: FnMut(&Map, &Position, &Direction),
 	{
-		let mut map = Map::new(rows, columns);
 		initial_peek(&map);
-		let mut rng = thread_rng();
-
-		let mut visited = HashSet::new();
-		visited.insert(start);
-		let mut walls = map.walls_around(&start);
-
-		while !walls.is_empty() {
-			let index = rng.gen_range(0, walls.len());
-			let (from, dir) = walls.remove(index);
-			if let Some(to) = map.move_in_direction(&from, &dir) {
-				if !visited.contains(&to) {
-					map.set(&from, &dir, false);
-
-					visited.insert(to);
-					walls.append(&mut map.walls_around(&to));
-
-					peek(&map, &from, &dir);
-				}
-			}
-		}
 
+		let mut grid = Grid::new(Square::new(&map));
+		let start = grid
+			.shape
+			.cell(&start)
+			.expect("The generation must start from a cell of the maze");
+		grid.carve(algorithm, start, rng, |grid, a, b| map.copy_wall(grid, a, b, &mut peek))
+			.expect("Only the binary tree and recursive division need a map of their own");
 		map
 	}
 
-	pub fn generate_ab<F, G>(rows: usize, columns: usize, start: Position, mut initial_peek: F, mut peek: G) -> Map
+	/// Sets the wall between two cells of `grid` the same in the map.
+	fn copy_wall<G>(&mut self, grid: &Grid<Square>, a: usize, b: usize, peek: &mut G)
 	where
+		G: FnMut(&Map, &Position, &Direction),
+	{
+		let (pos, dir) = (grid.shape.position(a), grid.shape.direction(a, b));
+		self.set(&pos, &dir, !grid.is_open(a, b));
+		peek(self, &pos, &dir);
+	}
+
+	pub fn generate_tree<R, F, G>(mut map: Map, rng: &mut R, mut initial_peek: F, mut peek: G) -> Map
+	where
+		R: Rng + ?Sized,
 		F: FnMut(&Map),
 		G: FnMut(&Map, &Position, &Direction),
 	{
-		let mut map = Map::new(rows, columns);
 		initial_peek(&map);
-		let mut rng = thread_rng();
-
-		let mut visited = HashSet::new();
-		visited.insert(start);
-		let mut current = start;
-
-		let mut moved_positions = Vec::with_capacity(4);
-		while visited.len() < rows * columns {
-			moved_positions.clear();
-			moved_positions.extend(
-				DIRECTIONS
-					.iter()
-					.filter_map(|d| map.move_in_direction(&current, d).map(|m| (m, d))),
-			);
-			if let Some(moved) = moved_positions.choose(&mut rng) {
-				if !visited.contains(&moved.0) {
-					map.set(&current, moved.1, false);
-					peek(&map, &current, moved.1);
-					visited.insert(moved.0);
-				}
-				current = moved.0;
-			}
+
+		// Every cell is joined to the cell to its left or the one above it, or
+		// to the only one of them that's there, along the top row and left side.
+		// The tree never crosses edges that wrap around.
+		let cells: Vec<_> = map.cells().collect();
+		for pos in cells {
+			let left = pos.1 > 0 && map.move_in_direction(&pos, &Direction::Left).is_some();
+			let up = pos.0 > 0 && map.move_in_direction(&pos, &Direction::Up).is_some();
+			let dir = match (left, up) {
+				(true, true) if rng.gen() => Direction::Left,
+				(true, true) => Direction::Up,
+				(true, false) => Direction::Left,
+				(false, true) => Direction::Up,
+				(false, false) => continue,
+			};
+			map.set(&pos, &dir, false);
+			peek(&map, &pos, &dir);
 		}
 
+		map.connect(rng, &mut peek);
 		map
 	}
 
-	pub fn generate_div<F, G>(rows: usize, columns: usize, mut initial_peek: F, mut peek: G) -> Map
+	pub fn generate_div<R, F, G>(mut map: Map, rng: &mut R, mut initial_peek: F, mut peek: G) -> Map
 	where
+		R: Rng + ?Sized,
 		F: FnMut(&Map),
 		G: FnMut(&Map, &Position, &Direction),
 	{
@@ -301,7 +469,7 @@ impl Map {
 				for r in upper_left.0..(lower_right.0 + 1) {
 					if r != passage {
 						map.set_right(&Position(r, div), true);
-						peek(&map, &Position(r, div), &Direction::Right);
+						peek(map, &Position(r, div), &Direction::Right);
 					}
 				}
 
@@ -331,7 +499,7 @@ impl Map {
 				for c in upper_left.1..(lower_right.1 + 1) {
 					if c != passage {
 						map.set_below(&Position(div, c), true);
-						peek(&map, &Position(div, c), &Direction::Down);
+						peek(map, &Position(div, c), &Direction::Down);
 					}
 				}
 
@@ -345,99 +513,130 @@ impl Map {
 			}
 		}
 
-		let mut map = Map::new_empty(rows, columns);
+		// Starts without any walls between the cells of the maze, but the walls
+		// across edges that wrap around stay, as the rectangle is divided.
+		for (pos, dir) in map.inner_walls() {
+			if map.across_edge(&pos, &dir).is_none() {
+				map.set(&pos, &dir, false);
+			}
+		}
 		initial_peek(&map);
-		let mut rng = thread_rng();
 
 		let upper_left = Position(0, 0);
 		let lower_right = Position(map.rows - 1, map.columns - 1);
-		recurse_vertical(&mut map, &mut rng, upper_left, lower_right, &mut peek);
+		recurse_vertical(&mut map, rng, upper_left, lower_right, &mut peek);
 
+		map.connect(rng, &mut peek);
 		map
 	}
 
-	pub fn generate_wilson<F, G>(rows: usize, columns: usize, start: Position, mut initial_peek: F, mut peek: G) -> Map
+	/// Every wall between two cells of the maze, as the cell to the left of
+	/// or above it and the direction to the other cell. Walls across edges
+	/// that wrap around are given from the last column or row.
+	fn inner_walls(&self) -> Vec<(Position, Direction)> {
+		self.cells()
+			.flat_map(|pos| {
+				self.neighbours(&pos)
+					.filter(|(dir, _)| *dir == Direction::Right || *dir == Direction::Down)
+					.map(move |(dir, _)| (pos, dir))
+			})
+			.collect()
+	}
+
+	/// Joins up the parts of a masked maze that generators working on the
+	/// whole rectangle cut off from each other.
+	fn connect<R, G>(&mut self, rng: &mut R, peek: &mut G)
 	where
-		F: FnMut(&Map),
+		R: Rng + ?Sized,
 		G: FnMut(&Map, &Position, &Direction),
 	{
-		let mut map = Map::new(rows, columns);
-		initial_peek(&map);
-		let mut rng = thread_rng();
+		if self.mask.is_some() {
+			self.join(rng, peek);
+		}
+	}
 
-		let mut in_map = HashSet::new();
-		in_map.insert(start);
-		let mut unvisited: Vec<_> = (0..rows)
-			.flat_map(|r| (0..columns).filter_map(move |c| Some(Position(r, c)).filter(|p| p != &start)))
-			.collect();
+	/// Opens random walls between parts of the maze that aren't yet joined,
+	/// until every cell can be reached.
+	fn join<R, G>(&mut self, rng: &mut R, peek: &mut G)
+	where
+		R: Rng + ?Sized,
+		G: FnMut(&Map, &Position, &Direction),
+	{
+		let mut grid = Grid::new(Square::new(self));
+		for (from, to) in self.passages() {
+			let (a, b) = (grid.shape.cell(&from).unwrap(), grid.shape.cell(&to).unwrap());
+			grid.set(a, b, false);
+		}
+		grid.carve(Algorithm::Kruskal, 0, rng, |grid, a, b| {
+			self.copy_wall(grid, a, b, peek)
+		})
+		.unwrap();
+	}
 
-		let mut path: Vec<(Position, Direction)> = Vec::new();
-		let mut moved_positions = Vec::with_capacity(4);
-		while !unvisited.is_empty() {
-			let mut current = unvisited[rng.gen_range(0, unvisited.len())];
-
-			while !in_map.contains(&current) {
-				moved_positions.clear();
-				moved_positions.extend(
-					DIRECTIONS
-						.iter()
-						.filter_map(|d| map.move_in_direction(&current, d).map(|m| (m, d))),
-				);
-				if let Some((next, direction)) = moved_positions.choose(&mut rng) {
-					if let Some(index) = path.iter().position(|p| &p.0 == next) {
-						for (p, d) in path.drain(index..).rev() {
-							map.set(&p, &d, true);
-							peek(&map, &p, &d);
-						}
-					} else {
-						map.set(&current, direction, false);
-						peek(&map, &current, direction);
-						path.push((current, **direction));
-					}
-					current = *next;
-				}
-			}
+	/// The row above the first is the last, if the top and bottom wrap around.
+	fn row_above(&self, row: usize) -> usize {
+		if row == 0 {
+			self.rows - 1
+		} else {
+			row - 1
+		}
+	}
+	/// The column left of the first is the last, if the sides wrap around.
+	fn column_left_of(&self, column: usize) -> usize {
+		if column == 0 {
+			self.columns - 1
+		} else {
+			column - 1
+		}
+	}
+	fn right_index(&self, pos: &Position) -> usize {
+		if pos.1 == self.columns - 1 {
+			assert!(self.wraps_horizontally() && pos.0 < self.rows);
 
-			for (p, _) in path.drain(..) {
-				in_map.insert(p);
-				unvisited.swap_remove(unvisited.iter().position(|u| u == &p).unwrap());
-			}
+			self.rows * 2 * self.columns - (self.rows + self.columns) + pos.0
+		} else {
+			assert!(pos.0 < self.rows && pos.1 < self.columns - 1);
+
+			(self.rows - 1) * self.columns + pos.0 * (self.columns - 1) + pos.1
 		}
+	}
+	fn below_index(&self, pos: &Position) -> usize {
+		if pos.0 == self.rows - 1 {
+			assert!(self.wraps_vertically() && pos.1 < self.columns);
 
-		map
+			self.rows * 2 * self.columns - self.columns + pos.1
+		} else {
+			assert!(pos.0 < self.rows - 1 && pos.1 < self.columns);
+
+			pos.0 * self.columns + pos.1
+		}
 	}
 
 	pub fn set_above(&mut self, pos: &Position, closed: bool) {
-		self.set_below(&Position(pos.0 - 1, pos.1), closed);
+		self.set_below(&Position(self.row_above(pos.0), pos.1), closed);
 	}
 	pub fn is_above(&self, pos: &Position) -> bool {
-		self.is_below(&Position(pos.0 - 1, pos.1))
+		self.is_below(&Position(self.row_above(pos.0), pos.1))
 	}
 	pub fn set_left(&mut self, pos: &Position, closed: bool) {
-		self.set_right(&Position(pos.0, pos.1 - 1), closed);
+		self.set_right(&Position(pos.0, self.column_left_of(pos.1)), closed);
 	}
 	pub fn is_left(&self, pos: &Position) -> bool {
-		self.is_right(&Position(pos.0, pos.1 - 1))
+		self.is_right(&Position(pos.0, self.column_left_of(pos.1)))
 	}
 	pub fn set_right(&mut self, pos: &Position, closed: bool) {
-		assert!(pos.0 < self.rows && pos.1 < self.columns - 1);
-
-		self.map[(self.rows - 1) * self.columns + pos.0 * (self.columns - 1) + pos.1] = closed;
+		let index = self.right_index(pos);
+		self.map[index] = closed;
 	}
 	pub fn is_right(&self, pos: &Position) -> bool {
-		assert!(pos.0 < self.rows && pos.1 < self.columns - 1);
-
-		self.map[(self.rows - 1) * self.columns + pos.0 * (self.columns - 1) + pos.1]
+		self.map[self.right_index(pos)]
 	}
 	pub fn set_below(&mut self, pos: &Position, closed: bool) {
-		assert!(pos.0 < self.rows - 1 && pos.1 < self.columns);
-
-		self.map[pos.0 * self.columns + pos.1] = closed;
+		let index = self.below_index(pos);
+		self.map[index] = closed;
 	}
 	pub fn is_below(&self, pos: &Position) -> bool {
-		assert!(pos.0 < self.rows - 1 && pos.1 < self.columns);
-
-		self.map[pos.0 * self.columns + pos.1]
+		self.map[self.below_index(pos)]
 	}
 
 	pub fn set(&mut self, pos: &Position, dir: &Direction, closed: bool) {
@@ -449,49 +648,189 @@ impl Map {
 		};
 	}
 	pub fn is(&self, pos: &Position, dir: &Direction) -> Option<bool> {
+		if pos.0 >= self.rows || pos.1 >= self.columns {
+			return None;
+		}
 		match dir {
-			Direction::Up if 0 < pos.0 && pos.0 < self.rows && pos.1 < self.columns => Some(self.is_above(pos)),
-			Direction::Left if pos.0 < self.rows && 0 < pos.1 && pos.1 < self.columns => Some(self.is_left(pos)),
-			Direction::Right if pos.0 < self.rows && pos.1 < self.columns - 1 => Some(self.is_right(pos)),
-			Direction::Down if pos.0 < self.rows - 1 && pos.1 < self.columns => Some(self.is_below(pos)),
+			Direction::Up if 0 < pos.0 || self.wraps_vertically() => Some(self.is_above(pos)),
+			Direction::Left if 0 < pos.1 || self.wraps_horizontally() => Some(self.is_left(pos)),
+			Direction::Right if pos.1 < self.columns - 1 || self.wraps_horizontally() => Some(self.is_right(pos)),
+			Direction::Down if pos.0 < self.rows - 1 || self.wraps_vertically() => Some(self.is_below(pos)),
 			_ => None,
 		}
 	}
 
-	fn move_in_direction(&self, current: &Position, dir: &Direction) -> Option<Position> {
+	/// The neighbouring cell in the direction, if there's one that's part of
+	/// the maze. Moving over an edge that wraps around leads to the other side.
+	pub fn move_in_direction(&self, current: &Position, dir: &Direction) -> Option<Position> {
+		let Position(r, c) = *current;
 		match dir {
-			Direction::Up if current.0 > 0 => Some(Position(current.0 - 1, current.1)),
-			Direction::Left if current.1 > 0 => Some(Position(current.0, current.1 - 1)),
-			Direction::Right if current.1 < self.columns - 1 => Some(Position(current.0, current.1 + 1)),
-			Direction::Down if current.0 < self.rows - 1 => Some(Position(current.0 + 1, current.1)),
+			Direction::Up if r > 0 => Some(Position(r - 1, c)),
+			Direction::Up if self.wraps_vertically() => Some(Position(self.rows - 1, c)),
+			Direction::Left if c > 0 => Some(Position(r, c - 1)),
+			Direction::Left if self.wraps_horizontally() => Some(Position(r, self.columns - 1)),
+			Direction::Right if c < self.columns - 1 => Some(Position(r, c + 1)),
+			Direction::Right if self.wraps_horizontally() => Some(Position(r, 0)),
+			Direction::Down if r < self.rows - 1 => Some(Position(r + 1, c)),
+			Direction::Down if self.wraps_vertically() => Some(Position(0, c)),
 			_ => None,
 		}
+		.filter(|p| !self.is_masked(p))
 	}
 
-	fn walls_around(&self, pos: &Position) -> Vec<(Position, Direction)> {
+	/// The same wall seen from the cell on the opposite edge, if the wall on
+	/// the `dir` side of `pos` is on an edge that wraps around.
+	pub fn across_edge(&self, pos: &Position, dir: &Direction) -> Option<(Position, Direction)> {
+		let other = self.move_in_direction(pos, dir)?;
+		let across = match dir {
+			Direction::Up | Direction::Down => (pos.0 as isize - other.0 as isize).abs() > 1,
+			_ => (pos.1 as isize - other.1 as isize).abs() > 1,
+		};
+		Some((other, dir.opposite())).filter(|_| across)
+	}
+
+	/// The cells next to `pos` that are part of the maze, with the direction
+	/// to each, in the order of [`DIRECTIONS`]. Everything that goes from cell
+	/// to cell, including [`Square`], goes through here.
+	pub fn neighbours(&self, pos: &Position) -> impl Iterator<Item = (Direction, Position)> + '_ {
+		let pos = *pos;
 		DIRECTIONS
 			.iter()
-			.filter_map(|dir| {
-				if self.is(pos, dir) == Some(true) {
-					return Some((*pos, *dir));
-				}
-				None
-			})
+			.filter_map(move |dir| self.move_in_direction(&pos, dir).map(|other| (*dir, other)))
+	}
+
+	pub fn openings(&self, pos: &Position) -> Vec<Direction> {
+		self.neighbours(pos)
+			.filter(|(dir, _)| self.is(pos, dir) == Some(false))
+			.map(|(dir, _)| dir)
 			.collect()
 	}
 
 	fn possible_moves_for(&self, pos: &Position) -> Vec<Position> {
-		DIRECTIONS
-			.iter()
-			.filter_map(|dir| {
-				if self.is(pos, dir) == Some(false) {
-					return self.move_in_direction(pos, dir);
-				}
-				None
+		self.neighbours(pos)
+			.filter(|(dir, _)| self.is(pos, dir) == Some(false))
+			.map(|(_, other)| other)
+			.collect()
+	}
+
+	/// Every pair of neighbouring cells without a wall between them, once each,
+	/// in row order.
+	pub fn passages(&self) -> Vec<(Position, Position)> {
+		self.cells()
+			.flat_map(|pos| {
+				self.neighbours(&pos)
+					.filter(|(dir, _)| *dir == Direction::Right || *dir == Direction::Down)
+					.filter(move |(dir, _)| self.is(&pos, dir) == Some(false))
+					.map(move |(_, other)| (pos, other))
 			})
 			.collect()
 	}
 
+	/// Whether there's a wall along the top of the cell at `pos`, `pos.0` may
+	/// be `rows` for the bottom border. Walls around the maze follow the
+	/// outline of its mask.
+	pub fn has_horizontal_wall(&self, pos: &Position) -> bool {
+		if self.wraps_vertically() && (pos.0 == 0 || pos.0 == self.rows) {
+			return self.is_below(&Position(self.rows - 1, pos.1));
+		}
+		let above = pos.0 > 0 && !self.is_masked(&Position(pos.0 - 1, pos.1));
+		let below = pos.0 < self.rows && !self.is_masked(pos);
+		match (above, below) {
+			(true, true) => self.is_below(&Position(pos.0 - 1, pos.1)),
+			(true, false) | (false, true) => true,
+			(false, false) => false,
+		}
+	}
+	/// Whether there's a wall along the left of the cell at `pos`, `pos.1` may
+	/// be `columns` for the right border.
+	pub fn has_vertical_wall(&self, pos: &Position) -> bool {
+		if self.wraps_horizontally() && (pos.1 == 0 || pos.1 == self.columns) {
+			return self.is_right(&Position(pos.0, self.columns - 1));
+		}
+		let left = pos.1 > 0 && !self.is_masked(&Position(pos.0, pos.1 - 1));
+		let right = pos.1 < self.columns && !self.is_masked(pos);
+		match (left, right) {
+			(true, true) => self.is_right(&Position(pos.0, pos.1 - 1)),
+			(true, false) | (false, true) => true,
+			(false, false) => false,
+		}
+	}
+
+	/// The junction at every corner, from the upper left corner of the first
+	/// cell, row by row, with each corner's walls leading out of it.
+	pub fn junctions(&self) -> Vec<Vec<WallJunction>> {
+		(0..=self.rows)
+			.map(|r| (0..=self.columns).map(|c| self.junction(&Position(r, c))).collect())
+			.collect()
+	}
+
+	/// The junction at the upper left corner of the cell at `corner`.
+	fn junction(&self, corner: &Position) -> WallJunction {
+		let Position(r, c) = *corner;
+		let mut junction = WallJunction::default();
+		junction.set_up(r > 0 && self.has_vertical_wall(&Position(r - 1, c)));
+		junction.set_left(c > 0 && self.has_horizontal_wall(&Position(r, c - 1)));
+		junction.set_right(c < self.columns && self.has_horizontal_wall(&Position(r, c)));
+		junction.set_down(r < self.rows && self.has_vertical_wall(&Position(r, c)));
+		junction
+	}
+
+	/// All walls, including the border, with walls in line with each other
+	/// merged. Each wall goes between two corners, where corner `Position(r, c)`
+	/// is the upper left corner of the cell at `Position(r, c)`.
+	pub fn wall_runs(&self) -> Vec<(Position, Position)> {
+		let mut runs = Vec::new();
+		for r in 0..=self.rows {
+			let mut start = None;
+			for c in 0..=self.columns {
+				let wall = c < self.columns && self.has_horizontal_wall(&Position(r, c));
+				match (wall, start) {
+					(true, None) => start = Some(c),
+					(false, Some(s)) => {
+						runs.push((Position(r, s), Position(r, c)));
+						start = None;
+					}
+					_ => {}
+				}
+			}
+		}
+		for c in 0..=self.columns {
+			let mut start = None;
+			for r in 0..=self.rows {
+				let wall = r < self.rows && self.has_vertical_wall(&Position(r, c));
+				match (wall, start) {
+					(true, None) => start = Some(r),
+					(false, Some(s)) => {
+						runs.push((Position(s, c), Position(r, c)));
+						start = None;
+					}
+					_ => {}
+				}
+			}
+		}
+		runs
+	}
+
+	/// The number of steps from `from` to every reachable cell.
+	pub fn distances(&self, from: Position) -> HashMap<Position, usize> {
+		assert!(from.0 < self.rows && from.1 < self.columns);
+
+		let mut distances = HashMap::new();
+		distances.insert(from, 0);
+		let mut to_visit = VecDeque::new();
+		to_visit.push_back(from);
+		while let Some(next) = to_visit.pop_front() {
+			let distance = distances[&next];
+			for moved in self.possible_moves_for(&next) {
+				if let Entry::Vacant(e) = distances.entry(moved) {
+					e.insert(distance + 1);
+					to_visit.push_back(moved);
+				}
+			}
+		}
+		distances
+	}
+
 	pub fn solve(&self, from: Position, to: Position) -> Option<Vec<Direction>> {
 		assert!(from.0 < self.rows && from.1 < self.columns);
 		assert!(to.0 < self.rows && to.1 < self.columns);
@@ -506,9 +845,10 @@ impl Map {
 		to_visit.push_back(from);
 
 		while let Some(next) = to_visit.pop_front() {
-			for moved in self.possible_moves_for(&next) {
+			for dir in self.openings(&next) {
+				let moved = self.move_in_direction(&next, &dir).unwrap();
 				if let Entry::Vacant(e) = from_to.entry(moved) {
-					e.insert(Some(next));
+					e.insert(Some((next, dir)));
 					if moved == to {
 						return Some(build_path(from_to, to));
 					}
@@ -520,165 +860,324 @@ impl Map {
 		None
 	}
 
+	/// The solution as lines through the middle of the cells, in cell widths
+	/// from the upper left corner. Where the path crosses an edge that wraps
+	/// around, one line goes out over the edge and the next comes in over the
+	/// opposite edge.
+	pub fn solution_lines(&self, from: Position, to: Position) -> Option<Vec<Vec<(f64, f64)>>> {
+		let center = |pos: &Position| (pos.1 as f64 + 0.5, pos.0 as f64 + 0.5);
+		let mut lines = vec![vec![center(&from)]];
+		let mut current = from;
+		for dir in self.solve(from, to)? {
+			let next = self.move_in_direction(&current, &dir).unwrap();
+			if self.across_edge(&current, &dir).is_some() {
+				let (dx, dy) = match dir {
+					Direction::Up => (0.0, -0.5),
+					Direction::Left => (-0.5, 0.0),
+					Direction::Right => (0.5, 0.0),
+					_ => (0.0, 0.5),
+				};
+				let ((x0, y0), (x1, y1)) = (center(&current), center(&next));
+				lines.last_mut().unwrap().push((x0 + dx, y0 + dy));
+				lines.push(vec![(x1 - dx, y1 - dy)]);
+			}
+			lines.last_mut().unwrap().push(center(&next));
+			current = next;
+		}
+		Some(lines)
+	}
+
+	/// The junctions at both ends of the wall on the `dir` side of `pos`.
 	pub fn get_chars(&self, pos: &Position, dir: &Direction) -> (char, char) {
-		if dir == &Direction::Left || dir == &Direction::Right {
-			let mut above = WallJunction::default();
-			let mut below = WallJunction::default();
+		let Position(r, c) = *pos;
+		let (first, second) = match dir {
+			Direction::Up => (Position(r, c), Position(r, c + 1)),
+			Direction::Left => (Position(r, c), Position(r + 1, c)),
+			Direction::Right => (Position(r, c + 1), Position(r + 1, c + 1)),
+			Direction::Down => (Position(r + 1, c), Position(r + 1, c + 1)),
+		};
+		(char::from(self.junction(&first)), char::from(self.junction(&second)))
+	}
+}
 
-			if self.is(pos, dir).unwrap_or(true) {
-				above.set_down(true);
-				below.set_up(true);
-			}
+fn build_path(mut from_to: HashMap<Position, Option<(Position, Direction)>>, to: Position) -> Vec<Direction> {
+	if let Some(Some((from, dir))) = from_to.remove(&to) {
+		let mut part = build_path(from_to, from);
+		part.push(dir);
+		part
+	} else {
+		Vec::new()
+	}
+}
 
-			if pos.0 != 0 && self.is(&Position(pos.0 - 1, pos.1), dir).unwrap_or(true) {
-				above.set_up(true);
-			}
-			if pos.0 != self.rows - 1 && self.is(&Position(pos.0 + 1, pos.1), dir).unwrap_or(true) {
-				below.set_down(true);
+impl fmt::Display for Map {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let lines: Vec<String> = self
+			.junctions()
+			.into_iter()
+			.map(|row| row.into_iter().map(|j| format!("{}", j)).collect())
+			.collect();
+		write!(f, "{}", lines.join("\n"))
+	}
+}
+
+impl Map {
+	/// Reads a map from the text its [`fmt::Display`] implementation writes.
+	pub fn from_text(text: &str) -> Result<Map, String> {
+		let lines: Vec<&str> = text.trim_end_matches(['\n', '\r']).lines().collect();
+		if lines.len() < 2 {
+			return Err(String::from("A maze must be at least two lines"));
+		}
+		let width = lines[0].chars().count();
+		if width < 2 {
+			return Err(String::from("A maze must be at least two characters wide"));
+		}
+
+		let mut junctions = Vec::with_capacity(lines.len());
+		for (l, line) in lines.iter().enumerate() {
+			let line = line.trim_end_matches('\r');
+			if line.chars().count() != width {
+				return Err(format!(
+					"Line {} is {} characters wide, expected {}",
+					l + 1,
+					line.chars().count(),
+					width
+				));
 			}
+			let row = line
+				.chars()
+				.enumerate()
+				.map(|(c, ch)| {
+					WallJunction::try_from(ch)
+						.map_err(|ch| format!("Unknown character '{}' at line {}, column {}", ch, l + 1, c + 1))
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			junctions.push(row);
+		}
 
-			if dir == &Direction::Left {
-				if pos.0 == 0 || self.is_above(pos) {
-					above.set_right(true);
-				}
-				if pos.0 == self.rows - 1 || self.is_below(pos) {
-					below.set_right(true);
+		let rows = lines.len() - 1;
+		let columns = width - 1;
+		let at = |l: usize, c: usize| format!("line {}, column {}", l + 1, c + 1);
+
+		// The wall from each junction to the one to its right, and to the one
+		// below it.
+		let mut horizontal = vec![vec![false; columns]; rows + 1];
+		let mut vertical = vec![vec![false; columns + 1]; rows];
+		for (l, row) in junctions.iter().enumerate() {
+			for (c, junction) in row.iter().enumerate() {
+				if (l == 0 && junction.is_up())
+					|| (l == rows && junction.is_down())
+					|| (c == 0 && junction.is_left())
+					|| (c == columns && junction.is_right())
+				{
+					return Err(format!("Wall leading out of the maze at {}", at(l, c)));
 				}
-				if pos.1 != 0 {
-					if pos.0 == 0 || self.is_above(&Position(pos.0, pos.1 - 1)) {
-						above.set_left(true);
+				if c < columns {
+					if junction.is_right() != row[c + 1].is_left() {
+						return Err(format!("Walls don't meet between {} and {}", at(l, c), at(l, c + 1)));
 					}
-					if pos.0 == self.rows - 1 || self.is_below(&Position(pos.0, pos.1 - 1)) {
-						below.set_left(true);
-					}
-				}
-			} else {
-				if pos.0 == 0 || self.is_above(pos) {
-					above.set_left(true);
+					horizontal[l][c] = junction.is_right();
 				}
-				if pos.0 == self.rows - 1 || self.is_below(pos) {
-					below.set_left(true);
-				}
-				if pos.1 != self.columns - 1 {
-					if pos.0 == 0 || self.is_above(&Position(pos.0, pos.1 + 1)) {
-						above.set_right(true);
-					}
-					if pos.0 == self.rows - 1 || self.is_below(&Position(pos.0, pos.1 + 1)) {
-						below.set_right(true);
+				if l < rows {
+					if junction.is_down() != junctions[l + 1][c].is_up() {
+						return Err(format!("Walls don't meet between {} and {}", at(l, c), at(l + 1, c)));
 					}
+					vertical[l][c] = junction.is_down();
 				}
 			}
+		}
 
-			(char::from(above), char::from(below))
-		} else {
-			let mut left = WallJunction::default();
-			let mut right = WallJunction::default();
-
-			if self.is(pos, dir).unwrap_or(true) {
-				left.set_right(true);
-				right.set_left(true);
-			}
-
-			if pos.1 != 0 && self.is(&Position(pos.0, pos.1 - 1), dir).unwrap_or(true) {
-				left.set_left(true);
+		for l in &[0, rows] {
+			if let Some(c) = horizontal[*l].iter().position(|wall| !wall) {
+				return Err(format!(
+					"The outer wall is open between {} and {}",
+					at(*l, c),
+					at(*l, c + 1)
+				));
 			}
-			if pos.1 != self.columns - 1 && self.is(&Position(pos.0, pos.1 + 1), dir).unwrap_or(true) {
-				right.set_right(true);
+		}
+		for c in &[0, columns] {
+			if let Some(l) = vertical.iter().position(|row| !row[*c]) {
+				return Err(format!(
+					"The outer wall is open between {} and {}",
+					at(l, *c),
+					at(l + 1, *c)
+				));
 			}
+		}
 
-			if dir == &Direction::Up {
-				if pos.1 == 0 || self.is_left(pos) {
-					left.set_down(true);
-				}
-				if pos.1 == self.columns - 1 || self.is_right(pos) {
-					right.set_down(true);
-				}
-				if pos.0 != 0 {
-					if pos.1 == 0 || self.is_left(&Position(pos.0 - 1, pos.1)) {
-						left.set_up(true);
-					}
-					if pos.1 == self.columns - 1 || self.is_right(&Position(pos.0 - 1, pos.1)) {
-						right.set_up(true);
-					}
-				}
-			} else {
-				if pos.1 == 0 || self.is_left(pos) {
-					left.set_up(true);
+		let mut map = Map::new(rows, columns);
+		for r in 0..rows {
+			for c in 0..columns {
+				if c < columns - 1 {
+					map.set_right(&Position(r, c), vertical[r][c + 1]);
 				}
-				if pos.1 == self.columns - 1 || self.is_right(pos) {
-					right.set_up(true);
-				}
-				if pos.0 != self.rows - 1 {
-					if pos.1 == 0 || self.is_left(&Position(pos.0 + 1, pos.1)) {
-						left.set_down(true);
-					}
-					if pos.1 == self.columns - 1 || self.is_right(&Position(pos.0 + 1, pos.1)) {
-						right.set_down(true);
-					}
+				if r < rows - 1 {
+					map.set_below(&Position(r, c), horizontal[r + 1][c]);
 				}
 			}
-
-			(char::from(left), char::from(right))
 		}
+		Ok(map)
 	}
 }
 
-fn build_path(mut from_to: HashMap<Position, Option<Position>>, to: Position) -> Vec<Direction> {
-	if let Some(Some(from)) = from_to.remove(&to) {
-		let mut part = build_path(from_to, from);
-		part.push(
-			match ((from.0 as isize) - (to.0 as isize), (from.1 as isize) - (to.1 as isize)) {
-				(1, 0) => Direction::Up,
-				(0, 1) => Direction::Left,
-				(0, -1) => Direction::Right,
-				(-1, 0) => Direction::Down,
-				(_, _) => panic!(),
-			},
-		);
-		part
-	} else {
-		Vec::new()
+impl FromStr for Map {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Map::from_text(s)
 	}
 }
 
-impl fmt::Display for Map {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		let mut above;
-		let mut below = Vec::with_capacity(self.columns + 1);
-
-		below.push(UPPER_LEFT);
-		below.append(&mut vec![HORIZONTAL; self.columns - 1]);
-		below.push(UPPER_RIGHT);
-
-		for r in 0..(self.rows - 1) {
-			above = below;
-			below = vec![WallJunction::default(); self.columns + 1];
-			below[0] = VERTICAL;
-			below[self.columns] = VERTICAL;
-
-			for c in 0..(self.columns - 1) {
-				above[c + 1].set_down(self.is_right(&Position(r, c)));
-				below[c + 1].set_up(self.is_right(&Position(r, c)));
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use crate::ascii;
+
+	#[test]
+	fn reads_back_the_text_of_generated_maps() {
+		for algorithm in &ALGORITHMS {
+			for (rows, columns) in &[(1, 1), (1, 4), (4, 1), (5, 7)] {
+				for seed in 0..5 {
+					let mut rng = StdRng::seed_from_u64(seed);
+					let map = Map::generate(
+						*algorithm,
+						*rows,
+						*columns,
+						Position(0, 0),
+						&mut rng,
+						|_| {},
+						|_, _, _| {},
+					);
+					let text = map.to_string();
+					assert!(Map::from_text(&text).as_ref() == Ok(&map), "{}", text);
+					let crlf = format!("{}\r\n", text.replace('\n', "\r\n"));
+					assert!(Map::from_text(&crlf).as_ref() == Ok(&map), "{}", text);
+				}
 			}
+		}
+	}
 
-			for c in 0..self.columns {
-				below[c].set_right(self.is_below(&Position(r, c)));
-				below[c + 1].set_left(self.is_below(&Position(r, c)));
+	#[test]
+	fn empty_maps_have_no_cells() {
+		for (rows, columns) in &[(0, 0), (0, 5), (5, 0)] {
+			assert_eq!(Map::new(*rows, *columns).cells().count(), 0);
+		}
+	}
+
+	fn error_of(text: &str) -> String {
+		match Map::from_text(text) {
+			Ok(_) => panic!("{:?} was read as a maze", text),
+			Err(e) => e,
+		}
+	}
+
+	#[test]
+	fn rejects_malformed_text() {
+		assert_eq!(error_of("┌┐"), "A maze must be at least two lines");
+		assert_eq!(error_of("┌\n└"), "A maze must be at least two characters wide");
+		assert_eq!(error_of("┌┬┐\n├┼┤\n└┴"), "Line 3 is 2 characters wide, expected 3");
+		assert_eq!(error_of("┌┬┐\n├x┤\n└┴┘"), "Unknown character 'x' at line 2, column 2");
+		assert_eq!(
+			error_of("┌┬┐\n├┼┼\n└┴┘"),
+			"Wall leading out of the maze at line 2, column 3"
+		);
+		assert_eq!(
+			error_of("┌┬┐\n├┤┤\n└┴┘"),
+			"Walls don't meet between line 2, column 2 and line 2, column 3"
+		);
+		assert_eq!(
+			error_of("╷ ╷\n│ │\n└─┘"),
+			"The outer wall is open between line 1, column 1 and line 1, column 2"
+		);
+	}
+
+	#[test]
+	fn reads_back_the_ascii_of_generated_maps() {
+		for algorithm in &ALGORITHMS {
+			for (rows, columns) in &[(1, 1), (1, 4), (4, 1), (5, 7)] {
+				let mut rng = StdRng::seed_from_u64(0);
+				let map = Map::generate(
+					*algorithm,
+					*rows,
+					*columns,
+					Position(0, 0),
+					&mut rng,
+					|_| {},
+					|_, _, _| {},
+				);
+				let text = ascii::render(&map);
+				let document = ascii::parse(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+				assert!(document.map == map, "{}", text);
+				assert_eq!(
+					(document.start, document.goal),
+					(Position(0, 0), Position(rows - 1, columns - 1))
+				);
 			}
+		}
+	}
+
+	/// Two by two cells with a single wall inside, below the upper left one.
+	fn one_inner_wall() -> Map {
+		let mut map = Map::new(2, 2);
+		map.set_right(&Position(0, 0), false);
+		map.set_below(&Position(0, 1), false);
+		map.set_right(&Position(1, 0), false);
+		map
+	}
 
-			writeln!(f, "{}", above.into_iter().map(|j| format!("{}", j)).collect::<String>())?;
+	#[test]
+	fn reads_ascii_with_cells_of_any_size() {
+		let expected = one_inner_wall();
+
+		let wide = "+---+---+\n| S     |\n+---+   +\n|     G |\n+---+---+\n";
+		let tall = "+-+-+\n|.  |\n|   |\n+-+ +\n|   |\n|   |\n+-+-+\n";
+		for text in &[wide, tall] {
+			let document = ascii::parse(text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+			assert!(document.map == expected, "{}", text);
 		}
+	}
 
-		above = below;
-		below = vec![HORIZONTAL; self.columns + 1];
-		below[0] = LOWER_LEFT;
-		below[self.columns] = LOWER_RIGHT;
-		for c in 0..(self.columns - 1) {
-			above[c + 1].set_down(self.is_right(&Position(self.rows - 1, c)));
-			below[c + 1].set_up(self.is_right(&Position(self.rows - 1, c)));
+	#[test]
+	fn reads_gaps_in_the_outer_wall_of_ascii_as_the_ends() {
+		let text = "+  +--+\n|     |\n+--+  +\n|      \n+--+--+\n";
+		let document = ascii::parse(text).unwrap();
+		assert_eq!((document.start, document.goal), (Position(0, 0), Position(1, 1)));
+		assert!(document.map == one_inner_wall(), "{}", text);
+	}
+
+	fn ascii_error_of(text: &str) -> String {
+		match ascii::parse(text) {
+			Ok(_) => panic!("{:?} was read as a maze", text),
+			Err(e) => e,
 		}
+	}
 
-		writeln!(f, "{}", above.into_iter().map(|j| format!("{}", j)).collect::<String>())?;
-		write!(f, "{}", below.into_iter().map(|j| format!("{}", j)).collect::<String>())
+	#[test]
+	fn rejects_malformed_ascii() {
+		assert_eq!(
+			ascii_error_of("|  |\n+--+\n"),
+			"A maze must start with a line of corners, `+--+`"
+		);
+		assert_eq!(
+			ascii_error_of("+--+\n|  |\n"),
+			"A maze must end with a line of corners, `+--+`"
+		);
+		assert_eq!(
+			ascii_error_of("+--+--+\n|     |\n+--+-+-\n"),
+			"Expected a corner, `+`, at line 3, column 7"
+		);
+		assert_eq!(
+			ascii_error_of("+--+--+\n|     |\n+- +--+\n"),
+			"Expected all of a wall or none of it at line 3, column 3"
+		);
+		assert_eq!(
+			ascii_error_of("+--+--+\n|  |  |\n|     |\n+--+--+\n"),
+			"Expected all of a wall or none of it at line 3, column 4"
+		);
 	}
 }
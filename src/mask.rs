@@ -0,0 +1,315 @@
+use crate::map::Position;
+
+/// The shape of a maze, as the cells of a rectangle that are left out of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+	pub rows: usize,
+	pub columns: usize,
+	/// Whether each cell is left out, row by row.
+	excluded: Box<[bool]>,
+}
+
+impl Mask {
+	/// Makes a mask from which cells are part of the maze, trimmed to the
+	/// smallest rectangle around them. The cells have to form a single shape,
+	/// connected through their sides, for a maze to reach all of them.
+	fn new(included: Vec<Vec<bool>>) -> Result<Mask, String> {
+		let rows: Vec<usize> = (0..included.len()).filter(|r| included[*r].contains(&true)).collect();
+		let width = included.iter().map(|row| row.len()).max().unwrap_or(0);
+		let columns: Vec<usize> = (0..width)
+			.filter(|c| included.iter().any(|row| row.get(*c) == Some(&true)))
+			.collect();
+		let (top, left) = match (rows.first(), columns.first()) {
+			(Some(top), Some(left)) => (*top, *left),
+			_ => return Err(String::from("The mask doesn't include any cells")),
+		};
+
+		let mask = Mask {
+			rows: rows[rows.len() - 1] - top + 1,
+			columns: columns[columns.len() - 1] - left + 1,
+			excluded: (top..=rows[rows.len() - 1])
+				.flat_map(|r| (left..=columns[columns.len() - 1]).map(move |c| (r, c)))
+				.map(|(r, c)| included[r].get(c) != Some(&true))
+				.collect(),
+		};
+
+		let first = mask.cells().next().unwrap();
+		let mut reached = vec![false; mask.rows * mask.columns];
+		reached[first.0 * mask.columns + first.1] = true;
+		let mut to_visit = vec![first];
+		while let Some(Position(r, c)) = to_visit.pop() {
+			let neighbours = [(r.wrapping_sub(1), c), (r, c.wrapping_sub(1)), (r, c + 1), (r + 1, c)];
+			for (r, c) in neighbours.iter().copied() {
+				if r < mask.rows
+					&& c < mask.columns
+					&& !mask.excluded[r * mask.columns + c]
+					&& !reached[r * mask.columns + c]
+				{
+					reached[r * mask.columns + c] = true;
+					to_visit.push(Position(r, c));
+				}
+			}
+		}
+		if let Some(pos) = mask.cells().find(|p| !reached[p.0 * mask.columns + p.1]) {
+			return Err(format!(
+				"The mask must be a single shape, but row {}, column {} isn't connected to the rest",
+				pos.0 + top + 1,
+				pos.1 + left + 1
+			));
+		}
+		Ok(mask)
+	}
+
+	pub fn is_excluded(&self, pos: &Position) -> bool {
+		self.excluded[pos.0 * self.columns + pos.1]
+	}
+
+	/// The cells that are part of the maze, row by row.
+	pub fn cells(&self) -> impl Iterator<Item = Position> + '_ {
+		(0..self.rows)
+			.flat_map(move |r| (0..self.columns).map(move |c| Position(r, c)))
+			.filter(move |p| !self.is_excluded(p))
+	}
+
+	/// The mask as text, one line per row, the way [`Mask::parse`] reads it.
+	#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+	pub fn lines(&self) -> Vec<String> {
+		(0..self.rows)
+			.map(|r| {
+				(0..self.columns)
+					.map(|c| if self.is_excluded(&Position(r, c)) { '.' } else { '#' })
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Reads a mask from a PBM or PGM image, or from text. In images every
+	/// pixel is a cell, and the black or dark pixels are part of the maze. In
+	/// text every character is a cell, `#` for the cells that are part of the
+	/// maze and `.` for the ones that aren't.
+	pub fn parse(bytes: &[u8]) -> Result<Mask, String> {
+		match bytes {
+			[b'P', b'1'..=b'6', ..] => Mask::from_netpbm(bytes),
+			_ => Mask::from_text(std::str::from_utf8(bytes).map_err(|_| "The mask is neither an image nor text")?),
+		}
+	}
+
+	fn from_text(text: &str) -> Result<Mask, String> {
+		let mut included = Vec::new();
+		for (l, line) in text.lines().enumerate() {
+			let mut row = Vec::new();
+			for (c, ch) in line.trim_end().chars().enumerate() {
+				match ch {
+					'#' => row.push(true),
+					'.' => row.push(false),
+					_ => {
+						return Err(format!(
+							"Unexpected character '{}' at line {}, column {}, masks are drawn with `#` and `.`",
+							ch,
+							l + 1,
+							c + 1
+						))
+					}
+				}
+			}
+			included.push(row);
+		}
+		Mask::new(included)
+	}
+
+	/// Reads a plain or raw PBM or PGM image.
+	fn from_netpbm(bytes: &[u8]) -> Result<Mask, String> {
+		let kind = bytes[1];
+		if kind == b'3' || kind == b'6' {
+			return Err(String::from(
+				"Color images can't be used as masks, save it as PBM or PGM",
+			));
+		}
+		let bitmap = kind == b'1' || kind == b'4';
+		let mut rest = &bytes[2..];
+		let width = header_number(&mut rest)?;
+		let height = header_number(&mut rest)?;
+		if width == 0 || height == 0 {
+			return Err(String::from("The image must be at least one pixel wide and high"));
+		}
+		let max = if bitmap { 1 } else { header_number(&mut rest)? };
+		if max == 0 || max > 65535 {
+			return Err(format!("Unexpected maximum gray value {}", max));
+		}
+		let too_short = || String::from("The image ends too early");
+
+		// The size comes from the file, so nothing is allocated up front.
+		let mut included = Vec::new();
+		match kind {
+			b'1' | b'2' => {
+				let mut values = rest
+					.split(|b| b.is_ascii_whitespace())
+					.filter(|t| !t.is_empty())
+					// Bitmaps don't need spaces between the pixels.
+					.flat_map(|t| if bitmap { t.chunks(1).collect() } else { vec![t] })
+					.map(|t| {
+						std::str::from_utf8(t)
+							.ok()
+							.and_then(|t| t.parse::<usize>().ok())
+							.ok_or_else(|| format!("Unexpected pixel value {:?}", String::from_utf8_lossy(t)))
+					});
+				for _ in 0..height {
+					let mut row = Vec::new();
+					for _ in 0..width {
+						let value = values.next().ok_or_else(too_short)??;
+						row.push(if bitmap { value == 1 } else { value * 2 < max });
+					}
+					included.push(row);
+				}
+			}
+			b'4' => {
+				// A single whitespace ends the header, and each row is padded to
+				// whole bytes.
+				let data = rest.get(1..).ok_or_else(too_short)?;
+				let stride = (width + 7) / 8;
+				if stride.checked_mul(height).map_or(true, |n| data.len() < n) {
+					return Err(too_short());
+				}
+				for row in data.chunks(stride).take(height) {
+					included.push((0..width).map(|c| row[c / 8] >> (7 - c % 8) & 1 == 1).collect());
+				}
+			}
+			_ => {
+				let data = rest.get(1..).ok_or_else(too_short)?;
+				let size = if max < 256 { 1 } else { 2 };
+				if width
+					.checked_mul(height)
+					.and_then(|n| n.checked_mul(size))
+					.map_or(true, |n| data.len() < n)
+				{
+					return Err(too_short());
+				}
+				for row in data.chunks(size * width).take(height) {
+					included.push(
+						row.chunks(size)
+							.map(|v| v.iter().fold(0, |n, b| n << 8 | *b as usize) * 2 < max)
+							.collect(),
+					);
+				}
+			}
+		}
+		Mask::new(included)
+	}
+}
+
+/// Reads a number from the header of a Netpbm image, skipping whitespace and
+/// comments before it.
+fn header_number(bytes: &mut &[u8]) -> Result<usize, String> {
+	loop {
+		match bytes.first() {
+			Some(b) if b.is_ascii_whitespace() => *bytes = &bytes[1..],
+			Some(b'#') => {
+				let end = bytes.iter().position(|b| *b == b'\n').unwrap_or(bytes.len());
+				*bytes = &bytes[end..];
+			}
+			_ => break,
+		}
+	}
+	let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+	let number = std::str::from_utf8(&bytes[..digits])
+		.unwrap()
+		.parse()
+		.map_err(|_| String::from("Expected a number in the image header"))?;
+	*bytes = &bytes[digits..];
+	Ok(number)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The shape all the images below are of, a T.
+	fn t() -> Mask {
+		Mask::parse(b"###\n.#.\n").unwrap()
+	}
+
+	fn image(header: &str, pixels: &[u8]) -> Vec<u8> {
+		let mut bytes = header.as_bytes().to_vec();
+		bytes.extend_from_slice(pixels);
+		bytes
+	}
+
+	#[test]
+	fn reads_plain_images() {
+		assert_eq!(Mask::parse(b"P1\n3 2\n1 1 1\n0 1 0\n"), Ok(t()));
+		// Bitmaps don't need spaces between the pixels.
+		assert_eq!(Mask::parse(b"P1\n3 2\n111\n010\n"), Ok(t()));
+		assert_eq!(Mask::parse(b"P2\n3 2\n255\n0 0 10\n200 100 255\n"), Ok(t()));
+	}
+
+	#[test]
+	fn reads_raw_images() {
+		assert_eq!(Mask::parse(&image("P4\n3 2\n", &[0b1110_0000, 0b0100_0000])), Ok(t()));
+		assert_eq!(
+			Mask::parse(&image("P5\n3 2\n255\n", &[0, 0, 10, 200, 100, 255])),
+			Ok(t())
+		);
+		// Above 255 every pixel takes two bytes, most significant first.
+		assert_eq!(
+			Mask::parse(&image("P5\n3 2\n1000\n", &[0, 0, 0, 0, 1, 243, 3, 232, 0, 10, 3, 232])),
+			Ok(t())
+		);
+	}
+
+	#[test]
+	fn skips_comments_in_the_header() {
+		assert_eq!(Mask::parse(b"P1\n# A T\n3 # wide\n2\n111\n010\n"), Ok(t()));
+		assert_eq!(
+			Mask::parse(&image("P5 # raw\n3 2\n# gray\n255\n", &[0, 0, 0, 255, 0, 255])),
+			Ok(t())
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_images() {
+		let too_short = Err(String::from("The image ends too early"));
+		assert_eq!(Mask::parse(b"P1\n3 2\n111\n01"), too_short);
+		assert_eq!(Mask::parse(&image("P4\n3 2\n", &[0b1110_0000])), too_short);
+		assert_eq!(Mask::parse(&image("P5\n3 2\n255\n", &[0, 0, 0, 255])), too_short);
+		assert_eq!(Mask::parse(b"P5\n3 2\n255"), too_short);
+
+		let color = Err(String::from(
+			"Color images can't be used as masks, save it as PBM or PGM",
+		));
+		assert_eq!(Mask::parse(b"P3\n1 1\n255\n0 0 0\n"), color);
+		assert_eq!(Mask::parse(&image("P6\n1 1\n255\n", &[0, 0, 0])), color);
+
+		let empty = Err(String::from("The image must be at least one pixel wide and high"));
+		assert_eq!(Mask::parse(b"P1\n0 2\n"), empty);
+		assert_eq!(Mask::parse(b"P5\n3 0\n255\n"), empty);
+
+		assert_eq!(
+			Mask::parse(b"P2\n2 1\n0\n0 0\n"),
+			Err(String::from("Unexpected maximum gray value 0"))
+		);
+		assert_eq!(
+			Mask::parse(b"P1\nthree 2\n"),
+			Err(String::from("Expected a number in the image header"))
+		);
+	}
+
+	#[test]
+	fn rejects_masks_in_parts() {
+		assert_eq!(
+			Mask::parse(b"P1\n3 2\n1 0 1\n1 0 0\n"),
+			Err(String::from(
+				"The mask must be a single shape, but row 1, column 3 isn't connected to the rest"
+			))
+		);
+		assert_eq!(
+			Mask::parse(b"#.\n.#\n"),
+			Err(String::from(
+				"The mask must be a single shape, but row 2, column 2 isn't connected to the rest"
+			))
+		);
+		assert_eq!(
+			Mask::parse(b"...\n"),
+			Err(String::from("The mask doesn't include any cells"))
+		);
+	}
+}
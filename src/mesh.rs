@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::map::{Map, Position};
+
+/// How to build a solid maze. Sizes are in millimetres.
+pub struct MeshOptions {
+	/// Width and depth of a cell, from the middle of one wall to the next.
+	pub cell_size: f64,
+	pub wall_thickness: f64,
+	/// Height of the walls above the base plate.
+	pub wall_height: f64,
+	pub base_thickness: f64,
+	/// Engraves the solution into the base plate.
+	pub groove: bool,
+	pub groove_depth: f64,
+}
+
+impl Default for MeshOptions {
+	fn default() -> Self {
+		MeshOptions {
+			cell_size: 20.0,
+			wall_thickness: 2.0,
+			wall_height: 10.0,
+			base_thickness: 2.0,
+			groove: false,
+			groove_depth: 1.0,
+		}
+	}
+}
+
+/// A triangle mesh, with every triangle's vertices counterclockwise seen
+/// from the outside.
+#[derive(Default)]
+pub struct Mesh {
+	vertices: Vec<[f64; 3]>,
+	indices: HashMap<[u64; 3], usize>,
+	triangles: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+	fn vertex(&mut self, v: [f64; 3]) -> usize {
+		let key = [v[0].to_bits(), v[1].to_bits(), v[2].to_bits()];
+		let vertices = &mut self.vertices;
+		*self.indices.entry(key).or_insert_with(|| {
+			vertices.push(v);
+			vertices.len() - 1
+		})
+	}
+
+	fn quad(&mut self, corners: [[f64; 3]; 4]) {
+		let [a, b, c, d] = corners.map(|v| self.vertex(v));
+		self.triangles.push([a, b, c]);
+		self.triangles.push([a, c, d]);
+	}
+
+	/// Adds the triangles of `other`, raised by `dz`.
+	fn append(&mut self, other: &Mesh, dz: f64) {
+		for triangle in &other.triangles {
+			let corners = triangle.map(|i| {
+				let [x, y, z] = other.vertices[i];
+				self.vertex([x, y, z + dz])
+			});
+			self.triangles.push(corners);
+		}
+	}
+
+	/// Binary STL.
+	pub fn to_stl(&self) -> Vec<u8> {
+		let mut stl = vec![0; 80];
+		stl[..14].copy_from_slice(b"maze_generator");
+		stl.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+		for triangle in &self.triangles {
+			let [a, b, c] = triangle.map(|i| self.vertices[i]);
+			let (u, v) = (sub(b, a), sub(c, a));
+			let normal = [
+				u[1] * v[2] - u[2] * v[1],
+				u[2] * v[0] - u[0] * v[2],
+				u[0] * v[1] - u[1] * v[0],
+			];
+			let length = normal.iter().map(|n| n * n).sum::<f64>().sqrt().max(f64::MIN_POSITIVE);
+			for value in normal
+				.iter()
+				.map(|n| n / length)
+				.chain(a.iter().chain(&b).chain(&c).copied())
+			{
+				stl.extend_from_slice(&(value as f32).to_le_bytes());
+			}
+			stl.extend_from_slice(&[0, 0]);
+		}
+		stl
+	}
+
+	/// Wavefront OBJ, in millimetres.
+	pub fn to_obj(&self) -> String {
+		let mut obj = String::from("# maze_generator\n");
+		for v in &self.vertices {
+			writeln!(obj, "v {} {} {}", v[0], v[1], v[2]).unwrap();
+		}
+		for t in &self.triangles {
+			writeln!(obj, "f {} {} {}", t[0] + 1, t[1] + 1, t[2] + 1).unwrap();
+		}
+		obj
+	}
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// Builds the maze as a single solid, a base plate with the walls standing on
+/// it, seen from above with the first row furthest away.
+pub fn build(map: &Map, start: Position, goal: Position, options: &MeshOptions) -> Mesh {
+	let half = options.wall_thickness / 2.0;
+	let width = map.columns as f64 * options.cell_size + options.wall_thickness;
+	let depth = map.rows as f64 * options.cell_size + options.wall_thickness;
+	let x = |column: usize| half + column as f64 * options.cell_size;
+	let y = |row: usize| depth - half - row as f64 * options.cell_size;
+
+	let base = options.base_thickness;
+	let top = base + options.wall_height;
+	let mut rects = Vec::new();
+	if options.groove {
+		if let Some(lines) = map.solution_lines(start, goal) {
+			let groove = options.cell_size / 8.0;
+			let point = |(px, py): (f64, f64)| (half + px * options.cell_size, depth - half - py * options.cell_size);
+			for pair in lines.iter().flat_map(|line| line.windows(2)) {
+				let ((x0, y0), (x1, y1)) = (point(pair[0]), point(pair[1]));
+				rects.push((
+					[
+						x0.min(x1) - groove,
+						y0.min(y1) - groove,
+						x0.max(x1) + groove,
+						y0.max(y1) + groove,
+					],
+					base - options.groove_depth,
+				));
+			}
+		}
+	}
+	for (from, to) in map.wall_runs() {
+		rects.push((
+			[x(from.1) - half, y(to.0) - half, x(to.1) + half, y(from.0) + half],
+			top,
+		));
+	}
+
+	heightfield(width, depth, base, &rects)
+}
+
+/// Builds a maze of several levels, `floors` from the bottom up, as one solid
+/// like [`build`] for each level. They're stacked with a gap as high as the
+/// walls between them, so that every floor can be seen into. `stairwells` are
+/// the cells of each floor with stairs down to the one below, which are holes
+/// through the base plate.
+pub fn build_levels(floors: &[Map], stairwells: &[Vec<Position>], options: &MeshOptions) -> Mesh {
+	let mut mesh = Mesh::default();
+	for (level, (map, stairwells)) in floors.iter().zip(stairwells).enumerate() {
+		let half = options.wall_thickness / 2.0;
+		let width = map.columns as f64 * options.cell_size + options.wall_thickness;
+		let depth = map.rows as f64 * options.cell_size + options.wall_thickness;
+		let x = |column: usize| half + column as f64 * options.cell_size;
+		let y = |row: usize| depth - half - row as f64 * options.cell_size;
+
+		let base = options.base_thickness;
+		let top = base + options.wall_height;
+		let mut rects = Vec::new();
+		for pos in stairwells {
+			rects.push((
+				[
+					x(pos.1) + half,
+					y(pos.0 + 1) + half,
+					x(pos.1 + 1) - half,
+					y(pos.0) - half,
+				],
+				0.0,
+			));
+		}
+		for (from, to) in map.wall_runs() {
+			rects.push((
+				[x(from.1) - half, y(to.0) - half, x(to.1) + half, y(from.0) + half],
+				top,
+			));
+		}
+
+		mesh.append(
+			&heightfield(width, depth, base, &rects),
+			level as f64 * (top + options.wall_height),
+		);
+	}
+	mesh
+}
+
+/// Edges closer than this are the same, to not leave slivers from rounding.
+const EPSILON: f64 = 1e-9;
+
+/// A solid over `0..width` × `0..depth` that's `height` high, except for the
+/// rectangles, as left, bottom, right and top, that are raised or lowered to
+/// their own height. Later rectangles are on top of earlier ones, and those
+/// with no height are holes through the solid.
+fn heightfield(width: f64, depth: f64, height: f64, rects: &[([f64; 4], f64)]) -> Mesh {
+	let edges = |i: usize, j: usize, end: f64| {
+		let mut edges: Vec<f64> = rects.iter().flat_map(|(r, _)| vec![r[i], r[j]]).collect();
+		edges.push(0.0);
+		edges.push(end);
+		edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		edges.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+		edges
+	};
+	let xs = edges(0, 2, width);
+	let ys = edges(1, 3, depth);
+	let index = |edges: &[f64], v: f64| edges.partition_point(|e| *e < v - EPSILON);
+
+	let mut heights = vec![vec![height; xs.len() - 1]; ys.len() - 1];
+	for (r, h) in rects {
+		for row in &mut heights[index(&ys, r[1])..index(&ys, r[3])] {
+			for cell in &mut row[index(&xs, r[0])..index(&xs, r[2])] {
+				*cell = *h;
+			}
+		}
+	}
+	let at = |i: isize, j: isize| {
+		if i < 0 || j < 0 || i as usize >= xs.len() - 1 || j as usize >= ys.len() - 1 {
+			0.0
+		} else {
+			heights[j as usize][i as usize]
+		}
+	};
+
+	// Sides are split at every height, so that every edge is shared by
+	// exactly two faces.
+	let mut levels: Vec<f64> = heights.iter().flatten().copied().chain(vec![0.0]).collect();
+	levels.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	levels.dedup();
+	let bands = |low: f64, high: f64| {
+		let levels: Vec<f64> = levels.iter().copied().filter(|l| *l >= low && *l <= high).collect();
+		levels.windows(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>()
+	};
+
+	let mut mesh = Mesh::default();
+	for j in 0..ys.len() - 1 {
+		let (y0, y1) = (ys[j], ys[j + 1]);
+		for i in 0..xs.len() - 1 {
+			let (x0, x1) = (xs[i], xs[i + 1]);
+			let h = heights[j][i];
+			if h > 0.0 {
+				mesh.quad([[x0, y0, h], [x1, y0, h], [x1, y1, h], [x0, y1, h]]);
+				mesh.quad([[x0, y0, 0.0], [x0, y1, 0.0], [x1, y1, 0.0], [x1, y0, 0.0]]);
+			}
+		}
+
+		for (i, x) in xs.iter().enumerate() {
+			let (left, right) = (at(i as isize - 1, j as isize), at(i as isize, j as isize));
+			for (low, high) in bands(left.min(right), left.max(right)) {
+				if left > right {
+					mesh.quad([[*x, y0, low], [*x, y1, low], [*x, y1, high], [*x, y0, high]]);
+				} else {
+					mesh.quad([[*x, y1, low], [*x, y0, low], [*x, y0, high], [*x, y1, high]]);
+				}
+			}
+		}
+	}
+	for i in 0..xs.len() - 1 {
+		let (x0, x1) = (xs[i], xs[i + 1]);
+		for (j, y) in ys.iter().enumerate() {
+			let (below, above) = (at(i as isize, j as isize - 1), at(i as isize, j as isize));
+			for (low, high) in bands(below.min(above), below.max(above)) {
+				if below > above {
+					mesh.quad([[x1, *y, low], [x0, *y, low], [x0, *y, high], [x1, *y, high]]);
+				} else {
+					mesh.quad([[x0, *y, low], [x1, *y, low], [x1, *y, high], [x0, *y, high]]);
+				}
+			}
+		}
+	}
+	mesh
+}
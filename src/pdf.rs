@@ -0,0 +1,86 @@
+/// Writes a PDF of vector pages, with Helvetica as the only font.
+#[derive(Default)]
+pub struct Pdf {
+	/// Each page's size, in points, and its content stream.
+	pages: Vec<(f64, f64, String)>,
+}
+
+impl Pdf {
+	/// Adds a page drawn by `content`, PDF operators with the origin in the
+	/// lower left corner. The font is available as `/F1`.
+	pub fn add_page(&mut self, width: f64, height: f64, content: String) {
+		self.pages.push((width, height, content));
+	}
+
+	pub fn pages(&self) -> usize {
+		self.pages.len()
+	}
+
+	pub fn finish(self) -> Vec<u8> {
+		let mut objects = vec![
+			String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+			format!(
+				"<< /Type /Pages /Kids [{}] /Count {} >>",
+				(0..self.pages.len())
+					.map(|i| format!("{} 0 R", 4 + 2 * i))
+					.collect::<Vec<_>>()
+					.join(" "),
+				self.pages.len()
+			),
+			String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"),
+		];
+		for (i, (width, height, content)) in self.pages.iter().enumerate() {
+			objects.push(format!(
+				"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+				width,
+				height,
+				5 + 2 * i
+			));
+			objects.push(format!(
+				"<< /Length {} >>\nstream\n{}\nendstream",
+				content.len(),
+				content
+			));
+		}
+
+		let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+		let mut offsets = Vec::with_capacity(objects.len());
+		for (i, object) in objects.iter().enumerate() {
+			offsets.push(pdf.len());
+			pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+		}
+		let xref = pdf.len();
+		pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+		for offset in offsets {
+			pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+		}
+		pdf.extend_from_slice(
+			format!(
+				"trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+				objects.len() + 1,
+				xref
+			)
+			.as_bytes(),
+		);
+		pdf
+	}
+}
+
+/// A string for a text operator. Characters outside of Latin-1 are replaced,
+/// as the font is only encoded for those.
+pub fn text(s: &str) -> String {
+	let mut escaped = String::from("(");
+	for c in s.chars() {
+		match c {
+			'(' | ')' | '\\' => {
+				escaped.push('\\');
+				escaped.push(c);
+			}
+			' '..='~' => escaped.push(c),
+			'\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", c as u32)),
+			_ => escaped.push('?'),
+		}
+	}
+	escaped.push(')');
+	escaped
+}
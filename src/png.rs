@@ -0,0 +1,197 @@
+use crate::raster::Image;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Encodes an image as an 8-bit RGB PNG.
+pub fn encode(image: &Image) -> Vec<u8> {
+	let mut header = Vec::with_capacity(13);
+	header.extend_from_slice(&(image.width as u32).to_be_bytes());
+	header.extend_from_slice(&(image.height as u32).to_be_bytes());
+	// Bit depth, color type (RGB), compression, filter and interlace method.
+	header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+	let mut scanlines = Vec::with_capacity(image.height * (image.width * 3 + 1));
+	for row in image.pixels.chunks(image.width.max(1)).take(image.height) {
+		scanlines.push(0);
+		for pixel in row {
+			scanlines.extend_from_slice(pixel);
+		}
+	}
+
+	let mut png = SIGNATURE.to_vec();
+	write_chunk(&mut png, b"IHDR", &header);
+	write_chunk(&mut png, b"IDAT", &zlib(&scanlines));
+	write_chunk(&mut png, b"IEND", &[]);
+	png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = png.len();
+	png.extend_from_slice(kind);
+	png.extend_from_slice(data);
+	let crc = crc32(&png[start..]);
+	png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+	let mut table = [0u32; 256];
+	for (n, entry) in table.iter_mut().enumerate() {
+		let mut c = n as u32;
+		for _ in 0..8 {
+			c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+		}
+		*entry = c;
+	}
+
+	!data
+		.iter()
+		.fold(!0u32, |c, b| table[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+fn adler32(data: &[u8]) -> u32 {
+	let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+		let a = (a + *byte as u32) % 65521;
+		(a, (b + a) % 65521)
+	});
+	(b << 16) | a
+}
+
+/// Wraps a deflate stream in a zlib header and checksum.
+fn zlib(data: &[u8]) -> Vec<u8> {
+	let mut out = vec![0x78, 0x01];
+	out.extend(deflate(data));
+	out.extend_from_slice(&adler32(data).to_be_bytes());
+	out
+}
+
+/// Writes bits least significant bit first, as deflate expects.
+#[derive(Default)]
+pub struct BitWriter {
+	bytes: Vec<u8>,
+	buffer: u32,
+	count: u32,
+}
+
+impl BitWriter {
+	pub fn write_bits(&mut self, value: u32, count: u32) {
+		self.buffer |= value << self.count;
+		self.count += count;
+		while self.count >= 8 {
+			self.bytes.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.count -= 8;
+		}
+	}
+	/// Huffman codes are stored most significant bit first.
+	fn write_code(&mut self, code: u32, length: u32) {
+		let reversed = (0..length).fold(0, |r, i| (r << 1) | ((code >> i) & 1));
+		self.write_bits(reversed, length);
+	}
+	pub fn finish(mut self) -> Vec<u8> {
+		if self.count > 0 {
+			self.bytes.push(self.buffer as u8);
+		}
+		self.bytes
+	}
+}
+
+const LENGTH_BASE: [usize; 29] = [
+	3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+	0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [usize; 30] = [
+	1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+	8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+	0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_SIZE: usize = 1 << 15;
+
+/// Compresses `data` as a single deflate block with the fixed Huffman codes,
+/// finding repeats with a hash chain.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+	let mut writer = BitWriter::default();
+	// Final block, fixed Huffman codes.
+	writer.write_bits(1, 1);
+	writer.write_bits(1, 2);
+
+	let hash =
+		|i: usize| ((data[i] as usize) << 10 ^ (data[i + 1] as usize) << 5 ^ data[i + 2] as usize) & (HASH_SIZE - 1);
+	let mut head = vec![usize::MAX; HASH_SIZE];
+	let mut previous = vec![usize::MAX; data.len()];
+	let insert = |i: usize, head: &mut [usize], previous: &mut [usize]| {
+		if i + MIN_MATCH <= data.len() {
+			let h = hash(i);
+			previous[i] = head[h];
+			head[h] = i;
+		}
+	};
+
+	let mut i = 0;
+	while i < data.len() {
+		let mut best = (0, 0);
+		if i + MIN_MATCH <= data.len() {
+			let mut candidate = head[hash(i)];
+			let mut chain = 0;
+			while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+				let length = data[candidate..]
+					.iter()
+					.zip(&data[i..])
+					.take(MAX_MATCH)
+					.take_while(|(a, b)| a == b)
+					.count();
+				if length > best.0 {
+					best = (length, i - candidate);
+				}
+				candidate = previous[candidate];
+				chain += 1;
+			}
+		}
+
+		if best.0 >= MIN_MATCH {
+			write_length(&mut writer, best.0);
+			write_distance(&mut writer, best.1);
+			for j in i..i + best.0 {
+				insert(j, &mut head, &mut previous);
+			}
+			i += best.0;
+		} else {
+			write_literal(&mut writer, data[i] as u32);
+			insert(i, &mut head, &mut previous);
+			i += 1;
+		}
+	}
+
+	write_literal(&mut writer, 256);
+	writer.finish()
+}
+
+fn write_literal(writer: &mut BitWriter, value: u32) {
+	match value {
+		0..=143 => writer.write_code(0x30 + value, 8),
+		144..=255 => writer.write_code(0x190 + value - 144, 9),
+		256..=279 => writer.write_code(value - 256, 7),
+		_ => writer.write_code(0xc0 + value - 280, 8),
+	}
+}
+
+fn write_length(writer: &mut BitWriter, length: usize) {
+	let index = LENGTH_BASE.iter().rposition(|base| *base <= length).unwrap();
+	write_literal(writer, 257 + index as u32);
+	writer.write_bits((length - LENGTH_BASE[index]) as u32, LENGTH_EXTRA[index]);
+}
+
+fn write_distance(writer: &mut BitWriter, distance: usize) {
+	let index = DISTANCE_BASE.iter().rposition(|base| *base <= distance).unwrap();
+	writer.write_code(index as u32, 5);
+	writer.write_bits((distance - DISTANCE_BASE[index]) as u32, DISTANCE_EXTRA[index]);
+}
@@ -0,0 +1,160 @@
+use std::f64::consts::PI;
+
+use crate::grid::{on_circle, Shape, Side};
+use crate::map::Position;
+
+/// Rings of cells around a single cell in the middle, where each ring is one
+/// cell wide. Rings are split into more cells further out, so that the cells
+/// stay about as wide as they're deep.
+pub struct Polar {
+	/// The number of cells in each ring, from the middle out.
+	rings: Vec<usize>,
+	/// The number of the first cell of each ring.
+	offsets: Vec<usize>,
+}
+
+impl Polar {
+	pub fn new(rings: usize) -> Result<Polar, String> {
+		if rings == 0 {
+			return Err(String::from("A circular maze needs at least one ring"));
+		}
+		let mut counts = vec![1];
+		for ring in 1..rings {
+			let previous = counts[ring - 1];
+			// The inside of the ring is `ring` from the middle, so each of the
+			// previous ring's cells would be this wide out here.
+			let width = 2.0 * PI * ring as f64 / previous as f64;
+			counts.push(previous * (width.round() as usize).max(1));
+		}
+		let offsets = counts
+			.iter()
+			.scan(0, |offset, count| {
+				*offset += count;
+				Some(*offset - count)
+			})
+			.collect();
+		Ok(Polar { rings: counts, offsets })
+	}
+
+	/// The ring of the cell and its place in the ring, clockwise from the top.
+	pub fn position(&self, cell: usize) -> Position {
+		let ring = self.offsets.iter().rposition(|o| *o <= cell).unwrap();
+		Position(ring, cell - self.offsets[ring])
+	}
+
+	/// The angles of the cell's sides going clockwise, in radians from the
+	/// right.
+	fn angles(&self, pos: &Position) -> (f64, f64) {
+		let step = 2.0 * PI / self.rings[pos.0] as f64;
+		(pos.1 as f64 * step - PI / 2.0, (pos.1 + 1) as f64 * step - PI / 2.0)
+	}
+
+	/// How many cells further out each cell of the ring borders, 0 for the
+	/// outermost ring.
+	fn split(&self, ring: usize) -> usize {
+		self.rings.get(ring + 1).map_or(0, |next| next / self.rings[ring])
+	}
+
+	fn middle(&self) -> (f64, f64) {
+		let radius = self.rings.len() as f64;
+		(radius, radius)
+	}
+}
+
+impl Shape for Polar {
+	const ENTRANCE: bool = true;
+
+	fn count(&self) -> usize {
+		self.rings.iter().sum()
+	}
+
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)> {
+		let pos = self.position(cell);
+		let Position(ring, index) = pos;
+		let (first, last) = self.angles(&pos);
+		let center = self.middle();
+		let arc = |radius: usize, from: f64, to: f64| Side::Arc {
+			center,
+			radius: radius as f64,
+			from,
+			to,
+		};
+		let cell_at = |ring: usize, index: usize| self.offsets[ring] + index % self.rings[ring];
+
+		let mut sides = Vec::new();
+		let count = self.rings[ring];
+		if ring > 0 {
+			sides.push((
+				Side::Line(
+					on_circle(center, ring as f64, first),
+					on_circle(center, (ring + 1) as f64, first),
+				),
+				Some(cell_at(ring, index + count - 1)),
+			));
+		}
+		// The outside is split between the cells of the next ring.
+		let split = self.split(ring);
+		if split == 0 {
+			sides.push((arc(ring + 1, first, last), None));
+		} else {
+			let step = (last - first) / split as f64;
+			for i in 0..split {
+				sides.push((
+					arc(ring + 1, first + i as f64 * step, first + (i + 1) as f64 * step),
+					Some(cell_at(ring + 1, index * split + i)),
+				));
+			}
+		}
+		if ring > 0 {
+			sides.push((
+				Side::Line(
+					on_circle(center, (ring + 1) as f64, last),
+					on_circle(center, ring as f64, last),
+				),
+				Some(cell_at(ring, index + 1)),
+			));
+			sides.push((
+				arc(ring, last, first),
+				Some(cell_at(ring - 1, index / self.split(ring - 1))),
+			));
+		}
+		sides
+	}
+
+	fn center(&self, cell: usize) -> (f64, f64) {
+		let pos = self.position(cell);
+		if pos.0 == 0 {
+			return self.middle();
+		}
+		let (first, last) = self.angles(&pos);
+		on_circle(self.middle(), pos.0 as f64 + 0.5, (first + last) / 2.0)
+	}
+
+	fn size(&self) -> (f64, f64) {
+		let diameter = 2.0 * self.rings.len() as f64;
+		(diameter, diameter)
+	}
+
+	fn cell(&self, pos: &Position) -> Option<usize> {
+		let count = self.rings.get(pos.0)?;
+		Some(self.offsets[pos.0] + pos.1).filter(|_| pos.1 < *count)
+	}
+
+	/// Starts at the top of the outermost ring, and goes to the middle.
+	fn ends(&self) -> (usize, usize) {
+		(self.offsets[self.rings.len() - 1], 0)
+	}
+
+	fn arrow(&self, from: usize, to: usize) -> char {
+		let (from, to) = (self.position(from), self.position(to));
+		if to.0 > from.0 {
+			'↑'
+		} else if to.0 < from.0 {
+			'↓'
+		} else if to.1 == (from.1 + 1) % self.rings[from.0] {
+			'↻'
+		} else {
+			'↺'
+		}
+	}
+}
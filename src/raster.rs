@@ -0,0 +1,207 @@
+use crate::draw::DrawOptions;
+use crate::map::{Map, Position};
+
+pub type Color = [u8; 3];
+
+/// Parses a hex color, `#rgb` or `#rrggbb`, or one of the basic CSS color
+/// names.
+pub fn parse_color(s: &str) -> Option<Color> {
+	let hex = |s: &str| u8::from_str_radix(s, 16).ok();
+	match s.trim().to_lowercase().as_str() {
+		"black" => Some([0, 0, 0]),
+		"white" => Some([255, 255, 255]),
+		"gray" | "grey" => Some([128, 128, 128]),
+		"silver" => Some([192, 192, 192]),
+		"red" => Some([255, 0, 0]),
+		"maroon" => Some([128, 0, 0]),
+		"orange" => Some([255, 165, 0]),
+		"yellow" => Some([255, 255, 0]),
+		"lime" => Some([0, 255, 0]),
+		"green" => Some([0, 128, 0]),
+		"teal" => Some([0, 128, 128]),
+		"cyan" | "aqua" => Some([0, 255, 255]),
+		"blue" => Some([0, 0, 255]),
+		"navy" => Some([0, 0, 128]),
+		"purple" => Some([128, 0, 128]),
+		"magenta" | "fuchsia" => Some([255, 0, 255]),
+		s if s.starts_with('#') && s.len() == 7 => Some([hex(&s[1..3])?, hex(&s[3..5])?, hex(&s[5..7])?]),
+		s if s.starts_with('#') && s.len() == 4 => {
+			let short = |i: usize| hex(&s[i..i + 1]).map(|v| v * 17);
+			Some([short(1)?, short(2)?, short(3)?])
+		}
+		_ => None,
+	}
+}
+
+/// An RGB image.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Image {
+	pub width: usize,
+	pub height: usize,
+	pub pixels: Vec<Color>,
+}
+
+impl Image {
+	pub fn new(width: usize, height: usize, color: Color) -> Image {
+		Image {
+			width,
+			height,
+			pixels: vec![color; width * height],
+		}
+	}
+
+	/// Paints every pixel whose center is inside the rectangle, blending the
+	/// color with what's already there by `alpha`.
+	pub fn blend_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: Color, alpha: f64) {
+		let columns = pixel_range(x0, x1, self.width);
+		let rows = pixel_range(y0, y1, self.height);
+		for y in rows {
+			for x in columns.clone() {
+				self.blend(x, y, color, alpha);
+			}
+		}
+	}
+	pub fn fill_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: Color) {
+		self.blend_rect(x0, y0, x1, y1, color, 1.0);
+	}
+
+	pub fn fill_circle(&mut self, cx: f64, cy: f64, radius: f64, color: Color) {
+		for y in pixel_range(cy - radius, cy + radius, self.height) {
+			for x in pixel_range(cx - radius, cx + radius, self.width) {
+				let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+				if dx * dx + dy * dy <= radius * radius {
+					self.blend(x, y, color, 1.0);
+				}
+			}
+		}
+	}
+
+	/// Paints every pixel whose center is closer to the line than half its
+	/// width, which rounds off its ends.
+	pub fn stroke_line(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, width: f64, color: Color) {
+		let radius = width / 2.0;
+		let (dx, dy) = (x1 - x0, y1 - y0);
+		let length = dx * dx + dy * dy;
+		for y in pixel_range(y0.min(y1) - radius, y0.max(y1) + radius, self.height) {
+			for x in pixel_range(x0.min(x1) - radius, x0.max(x1) + radius, self.width) {
+				let (px, py) = (x as f64 + 0.5 - x0, y as f64 + 0.5 - y0);
+				let t = if length > 0.0 {
+					((px * dx + py * dy) / length).clamp(0.0, 1.0)
+				} else {
+					0.0
+				};
+				let (ex, ey) = (px - t * dx, py - t * dy);
+				if ex * ex + ey * ey <= radius * radius {
+					self.blend(x, y, color, 1.0);
+				}
+			}
+		}
+	}
+
+	/// Paints every pixel whose center is inside the polygon, blending the
+	/// color with what's already there by `alpha`.
+	pub fn blend_polygon(&mut self, points: &[(f64, f64)], color: Color, alpha: f64) {
+		let top = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+		let bottom = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+		for y in pixel_range(top, bottom, self.height) {
+			let center = y as f64 + 0.5;
+			// Where the edges cross the middle of the row of pixels, from left
+			// to right, every other span is inside.
+			let mut crossings: Vec<f64> = points
+				.iter()
+				.zip(points.iter().cycle().skip(1))
+				.filter(|(a, b)| (a.1 <= center) != (b.1 <= center))
+				.map(|(a, b)| a.0 + (center - a.1) / (b.1 - a.1) * (b.0 - a.0))
+				.collect();
+			crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+			for span in crossings.chunks(2) {
+				if let [from, to] = span {
+					for x in pixel_range(*from, *to, self.width) {
+						self.blend(x, y, color, alpha);
+					}
+				}
+			}
+		}
+	}
+
+	fn blend(&mut self, x: usize, y: usize, color: Color, alpha: f64) {
+		let pixel = &mut self.pixels[y * self.width + x];
+		for i in 0..3 {
+			pixel[i] = (pixel[i] as f64 * (1.0 - alpha) + color[i] as f64 * alpha).round() as u8;
+		}
+	}
+}
+
+/// The pixels whose centers are in `from..to`.
+fn pixel_range(from: f64, to: f64, size: usize) -> std::ops::Range<usize> {
+	let start = (from - 0.5).ceil().max(0.0) as usize;
+	let end = ((to - 0.5).ceil().max(0.0) as usize).min(size);
+	start..end.max(start)
+}
+
+pub fn color_of(s: &str) -> Result<Color, String> {
+	parse_color(s).ok_or_else(|| format!("Unknown color \"{}\"", s))
+}
+
+/// Draws the maze the same way [`crate::svg::render`] does.
+pub fn rasterize(map: &Map, start: Position, goal: Position, options: &DrawOptions) -> Result<Image, String> {
+	let cell = options.cell_size;
+	let mut image = Image::new(
+		options.width(map).round() as usize,
+		options.height(map).round() as usize,
+		color_of(&options.background)?,
+	);
+
+	if options.heatmap {
+		let color = color_of(&options.heatmap_color)?;
+		let distances = map.distances(start);
+		let max = distances.values().copied().max().unwrap_or(0).max(1) as f64;
+		for (pos, distance) in &distances {
+			let (x, y) = (options.x(pos.1), options.y(pos.0));
+			image.blend_rect(x, y, x + cell, y + cell, color, *distance as f64 / max);
+		}
+	}
+
+	if options.solution {
+		if let Some(lines) = map.solution_lines(start, goal) {
+			let color = color_of(&options.solution_color)?;
+			let half = cell / 8.0;
+			for pair in lines.iter().flat_map(|line| line.windows(2)) {
+				let (x0, y0) = (options.margin + pair[0].0 * cell, options.margin + pair[0].1 * cell);
+				let (x1, y1) = (options.margin + pair[1].0 * cell, options.margin + pair[1].1 * cell);
+				image.fill_rect(
+					x0.min(x1) - half,
+					y0.min(y1) - half,
+					x0.max(x1) + half,
+					y0.max(y1) + half,
+					color,
+				);
+			}
+		}
+	}
+
+	if options.markers {
+		for (pos, color) in &[(start, &options.start_color), (goal, &options.goal_color)] {
+			image.fill_circle(
+				options.x(pos.1) + cell / 2.0,
+				options.y(pos.0) + cell / 2.0,
+				cell * 0.3,
+				color_of(color)?,
+			);
+		}
+	}
+
+	let color = color_of(&options.wall_color)?;
+	let half = options.wall_thickness / 2.0;
+	for (from, to) in map.wall_runs() {
+		image.fill_rect(
+			options.x(from.1) - half,
+			options.y(from.0) - half,
+			options.x(to.1) + half,
+			options.y(to.0) + half,
+			color,
+		);
+	}
+
+	Ok(image)
+}
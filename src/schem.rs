@@ -0,0 +1,189 @@
+use crate::map::{Direction, Map, Position};
+use crate::png;
+
+/// The Minecraft version the blocks are named for, 1.20.1. Newer versions
+/// upgrade older schematics when they're loaded.
+const DATA_VERSION: i32 = 3465;
+
+const AIR: &str = "minecraft:air";
+
+/// How to build the maze out of blocks.
+pub struct SchematicOptions {
+	pub wall_block: String,
+	pub floor_block: String,
+	/// Height of the walls in blocks, above the floor.
+	pub height: usize,
+	/// Leaves gaps in the outer wall next to the start and the goal.
+	pub openings: bool,
+}
+
+/// Builds the maze as a Sponge schematic (version 2), as read by WorldEdit
+/// and most other Minecraft tools. Walls and corridors are one block wide,
+/// with the first row to the north and the first column to the west.
+pub fn render(map: &Map, start: Position, goal: Position, options: &SchematicOptions) -> Result<Vec<u8>, String> {
+	let width = 2 * map.columns + 1;
+	let length = 2 * map.rows + 1;
+	let height = options.height + 1;
+	if [width, length, height].iter().any(|side| *side > u16::MAX as usize) {
+		return Err(format!(
+			"A schematic can't be larger than {} blocks in any direction",
+			u16::MAX
+		));
+	}
+
+	let mut gaps = Vec::new();
+	if options.openings {
+		let sides = [
+			(
+				start,
+				[Direction::Up, Direction::Left, Direction::Down, Direction::Right],
+			),
+			(
+				goal,
+				[Direction::Down, Direction::Right, Direction::Up, Direction::Left],
+			),
+		];
+		for (pos, order) in &sides {
+			if let Some(dir) = order.iter().find(|dir| map.is(pos, dir).is_none()) {
+				let (x, z) = (2 * pos.1 + 1, 2 * pos.0 + 1);
+				gaps.push(match dir {
+					Direction::Up => (x, 0),
+					Direction::Down => (x, length - 1),
+					Direction::Left => (0, z),
+					Direction::Right => (width - 1, z),
+				});
+			}
+		}
+	}
+
+	// Blocks are ordered by x, then z, then y, and refer to the palette. The
+	// same block is only in the palette once, even if it's used for more than
+	// one part of the maze.
+	let mut palette: Vec<String> = Vec::new();
+	let mut index_of = |name: String| match palette.iter().position(|n| *n == name) {
+		Some(i) => i as u8,
+		None => {
+			palette.push(name);
+			(palette.len() - 1) as u8
+		}
+	};
+	let air = index_of(String::from(AIR));
+	let floor = index_of(block(&options.floor_block));
+	let wall = index_of(block(&options.wall_block));
+	let mut blocks = vec![floor; width * length];
+	for _ in 0..options.height {
+		for z in 0..length {
+			for x in 0..width {
+				let is_wall = is_wall(map, x, z) && !gaps.contains(&(x, z));
+				blocks.push(if is_wall { wall } else { air });
+			}
+		}
+	}
+
+	let mut nbt = Nbt::default();
+	nbt.begin(COMPOUND, "Schematic");
+	nbt.int("Version", 2);
+	nbt.int("DataVersion", DATA_VERSION);
+	nbt.short("Width", width as u16);
+	nbt.short("Height", height as u16);
+	nbt.short("Length", length as u16);
+	nbt.int_array("Offset", &[0, 0, 0]);
+	nbt.int("PaletteMax", palette.len() as i32);
+	nbt.begin(COMPOUND, "Palette");
+	for (i, name) in palette.iter().enumerate() {
+		nbt.int(name, i as i32);
+	}
+	nbt.end();
+	// Palette indices are varints, every one of which fits in a byte here.
+	nbt.byte_array("BlockData", &blocks);
+	nbt.end();
+	Ok(gzip(&nbt.bytes))
+}
+
+/// Whether the block at `x`, `z` is part of a wall, where cells are at odd
+/// coordinates and the walls between them at even ones.
+fn is_wall(map: &Map, x: usize, z: usize) -> bool {
+	let (row, column) = (z / 2, x / 2);
+	match (x % 2, z % 2) {
+		(1, 1) => false,
+		(0, 1) => map.has_vertical_wall(&Position(row, column)),
+		(1, 0) => map.has_horizontal_wall(&Position(row, column)),
+		// Corners are only filled when a wall meets them, to not leave pillars
+		// in the middle of open areas.
+		_ => {
+			(column > 0 && map.has_horizontal_wall(&Position(row, column - 1)))
+				|| (column < map.columns && map.has_horizontal_wall(&Position(row, column)))
+				|| (row > 0 && map.has_vertical_wall(&Position(row - 1, column)))
+				|| (row < map.rows && map.has_vertical_wall(&Position(row, column)))
+		}
+	}
+}
+
+/// Blocks without a namespace are taken to be Minecraft's own.
+fn block(name: &str) -> String {
+	if name.contains(':') {
+		String::from(name)
+	} else {
+		format!("minecraft:{}", name)
+	}
+}
+
+const END: u8 = 0;
+const SHORT: u8 = 2;
+const INT: u8 = 3;
+const BYTE_ARRAY: u8 = 7;
+const COMPOUND: u8 = 10;
+const INT_ARRAY: u8 = 11;
+
+/// Writes uncompressed NBT, Minecraft's big-endian tagged binary format.
+#[derive(Default)]
+struct Nbt {
+	bytes: Vec<u8>,
+}
+
+impl Nbt {
+	fn begin(&mut self, tag: u8, name: &str) {
+		self.bytes.push(tag);
+		self.bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+		self.bytes.extend_from_slice(name.as_bytes());
+	}
+
+	fn end(&mut self) {
+		self.bytes.push(END);
+	}
+
+	/// Sizes are stored as shorts, but read as unsigned.
+	fn short(&mut self, name: &str, value: u16) {
+		self.begin(SHORT, name);
+		self.bytes.extend_from_slice(&value.to_be_bytes());
+	}
+
+	fn int(&mut self, name: &str, value: i32) {
+		self.begin(INT, name);
+		self.bytes.extend_from_slice(&value.to_be_bytes());
+	}
+
+	fn byte_array(&mut self, name: &str, values: &[u8]) {
+		self.begin(BYTE_ARRAY, name);
+		self.bytes.extend_from_slice(&(values.len() as i32).to_be_bytes());
+		self.bytes.extend_from_slice(values);
+	}
+
+	fn int_array(&mut self, name: &str, values: &[i32]) {
+		self.begin(INT_ARRAY, name);
+		self.bytes.extend_from_slice(&(values.len() as i32).to_be_bytes());
+		for value in values {
+			self.bytes.extend_from_slice(&value.to_be_bytes());
+		}
+	}
+}
+
+/// Wraps a deflate stream in a gzip header and trailer.
+fn gzip(data: &[u8]) -> Vec<u8> {
+	// No name or modification time, unknown operating system.
+	let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+	gzip.extend_from_slice(&png::deflate(data));
+	gzip.extend_from_slice(&png::crc32(data).to_le_bytes());
+	gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+	gzip
+}
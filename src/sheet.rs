@@ -0,0 +1,180 @@
+use std::fmt::Write;
+
+use crate::document::Document;
+use crate::map::{Map, Position};
+use crate::pdf::{self, Pdf};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Paper {
+	A4,
+	Letter,
+}
+
+impl Paper {
+	pub fn from_name(name: &str) -> Option<Paper> {
+		match name {
+			"a4" => Some(Paper::A4),
+			"letter" => Some(Paper::Letter),
+			_ => None,
+		}
+	}
+
+	/// Width and height in points.
+	fn size(&self) -> (f64, f64) {
+		match self {
+			Paper::A4 => (595.28, 841.89),
+			Paper::Letter => (612.0, 792.0),
+		}
+	}
+}
+
+pub struct SheetOptions {
+	pub title: String,
+	pub paper: Paper,
+	pub per_page: usize,
+}
+
+const MARGIN: f64 = 36.0;
+const TITLE_HEIGHT: f64 = 40.0;
+const LABEL_HEIGHT: f64 = 16.0;
+const GAP: f64 = 18.0;
+
+/// The most mazes on a page, six rows of six, that still leaves each of them
+/// some room on either paper size.
+pub const MAX_PER_PAGE: usize = 36;
+
+/// How hard a maze is to solve by hand, from the number of places along the
+/// solution where there's more than one way forward.
+pub fn difficulty(map: &Map) -> &'static str {
+	match map.stats().solution.map_or(0, |s| s.decision_points) {
+		0..=4 => "Easy",
+		5..=14 => "Medium",
+		15..=29 => "Hard",
+		_ => "Expert",
+	}
+}
+
+/// Lays the mazes out `per_page` to a page under the title, followed by pages
+/// with the same layout with the solutions drawn, as an answer key.
+pub fn render(mazes: &[Document], options: &SheetOptions) -> Vec<u8> {
+	let mut pdf = Pdf::default();
+	let (width, height) = options.paper.size();
+	let per_page = options.per_page.clamp(1, MAX_PER_PAGE);
+	for answers in &[false, true] {
+		let title = if *answers {
+			format!("{} - Answer key", options.title)
+		} else {
+			options.title.clone()
+		};
+		for (page, chunk) in mazes.chunks(per_page).enumerate() {
+			let mut content = String::new();
+			write_text(&mut content, MARGIN, height - MARGIN - 20.0, 20.0, &title);
+			write_text(
+				&mut content,
+				width - MARGIN - 60.0,
+				height - MARGIN - 20.0,
+				10.0,
+				&format!("Page {}", pdf.pages() + 1),
+			);
+
+			// Pages are taller than they're wide, so there are never more
+			// columns than rows.
+			let columns = ((per_page as f64).sqrt() as usize).max(1);
+			let rows = (per_page + columns - 1) / columns;
+			let slot_width = (width - 2.0 * MARGIN) / columns as f64;
+			let slot_height = (height - 2.0 * MARGIN - TITLE_HEIGHT) / rows as f64;
+			for (i, maze) in chunk.iter().enumerate() {
+				let number = page * per_page + i + 1;
+				let left = MARGIN + (i % columns) as f64 * slot_width;
+				let top = height - MARGIN - TITLE_HEIGHT - (i / columns) as f64 * slot_height;
+				let mut label = format!("{}.", number);
+				if let Some(seed) = maze.seed {
+					label.push_str(&format!("  Seed {}", seed));
+				}
+				label.push_str(&format!("  {}", difficulty(&maze.map)));
+				write_text(&mut content, left, top - 10.0, 10.0, &label);
+				write_maze(
+					&mut content,
+					maze,
+					left,
+					top - LABEL_HEIGHT,
+					slot_width - GAP,
+					slot_height - LABEL_HEIGHT - GAP,
+					*answers,
+				);
+			}
+			pdf.add_page(width, height, content);
+		}
+	}
+	pdf.finish()
+}
+
+fn write_text(content: &mut String, x: f64, y: f64, size: f64, s: &str) {
+	writeln!(
+		content,
+		"BT /F1 {} Tf {:.2} {:.2} Td {} Tj ET",
+		size,
+		x,
+		y,
+		pdf::text(s)
+	)
+	.unwrap();
+}
+
+/// Draws the maze as large as it fits in the box, centered horizontally.
+fn write_maze(content: &mut String, maze: &Document, left: f64, top: f64, width: f64, height: f64, solution: bool) {
+	let map = &maze.map;
+	let cell = (width / map.columns as f64).min(height / map.rows as f64);
+	let left = left + (width - cell * map.columns as f64) / 2.0;
+	let x = |column: usize| left + column as f64 * cell;
+	let y = |row: usize| top - row as f64 * cell;
+	let center = |pos: &Position| (x(pos.1) + cell / 2.0, y(pos.0) - cell / 2.0);
+
+	if solution {
+		if let Some(lines) = map.solution_lines(maze.start, maze.goal) {
+			writeln!(content, "1 0 0 RG {:.2} w 1 J 1 j", cell / 4.0).unwrap();
+			for line in lines {
+				for (i, (cx, cy)) in line.iter().enumerate() {
+					let (cx, cy) = (left + cx * cell, top - cy * cell);
+					writeln!(content, "{:.2} {:.2} {}", cx, cy, if i == 0 { "m" } else { "l" }).unwrap();
+				}
+			}
+			content.push_str("S\n");
+		}
+	}
+
+	for (pos, color) in &[(maze.start, "0 0.5 0"), (maze.goal, "0 0 1")] {
+		let (cx, cy) = center(pos);
+		writeln!(content, "{} rg", color).unwrap();
+		write_circle(content, cx, cy, cell * 0.3);
+	}
+
+	writeln!(content, "0 0 0 RG {:.2} w 2 J", (cell / 10.0).max(0.5)).unwrap();
+	for (from, to) in map.wall_runs() {
+		writeln!(
+			content,
+			"{:.2} {:.2} m {:.2} {:.2} l",
+			x(from.1),
+			y(from.0),
+			x(to.1),
+			y(to.0)
+		)
+		.unwrap();
+	}
+	content.push_str("S\n");
+}
+
+/// A filled circle, as four Bézier curves.
+fn write_circle(content: &mut String, cx: f64, cy: f64, r: f64) {
+	let k = r * 0.552_284_75;
+	writeln!(content, "{:.2} {:.2} m", cx + r, cy).unwrap();
+	for (x1, y1, x2, y2, x3, y3) in &[
+		(cx + r, cy + k, cx + k, cy + r, cx, cy + r),
+		(cx - k, cy + r, cx - r, cy + k, cx - r, cy),
+		(cx - r, cy - k, cx - k, cy - r, cx, cy - r),
+		(cx + k, cy - r, cx + r, cy - k, cx + r, cy),
+	] {
+		writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c", x1, y1, x2, y2, x3, y3).unwrap();
+	}
+	content.push_str("f\n");
+}
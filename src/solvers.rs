@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::map::{Direction, Map, Position, WallJunction, DIRECTIONS};
+
+/// A maze solving agent that only uses the information available from the
+/// cell it's standing on, as opposed to [`Map::solve`] which knows the whole
+/// map.
+pub trait Agent {
+	/// Takes a single step, returns `false` when the agent has stopped.
+	fn step(&mut self, map: &Map) -> bool;
+	fn steps(&self) -> usize;
+	fn is_solved(&self) -> bool;
+	/// The character used for `pos` when visualizing the agent.
+	fn mark(&self, pos: &Position) -> char;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strategy {
+	LeftHand,
+	RightHand,
+	Pledge,
+	Tremaux,
+	DeadEndFilling,
+}
+
+pub const STRATEGIES: [Strategy; 5] = [
+	Strategy::LeftHand,
+	Strategy::RightHand,
+	Strategy::Pledge,
+	Strategy::Tremaux,
+	Strategy::DeadEndFilling,
+];
+
+impl Strategy {
+	pub fn from_name(name: &str) -> Option<Strategy> {
+		match name {
+			"left" => Some(Strategy::LeftHand),
+			"right" => Some(Strategy::RightHand),
+			"pledge" => Some(Strategy::Pledge),
+			"tremaux" => Some(Strategy::Tremaux),
+			"fill" => Some(Strategy::DeadEndFilling),
+			_ => None,
+		}
+	}
+
+	pub fn agent(&self, map: &Map, from: Position, to: Position) -> Box<dyn Agent> {
+		match self {
+			Strategy::LeftHand => Box::new(WallFollower::new(from, to, Hand::Left)),
+			Strategy::RightHand => Box::new(WallFollower::new(from, to, Hand::Right)),
+			Strategy::Pledge => Box::new(Pledge::new(from, to, Direction::Right)),
+			Strategy::Tremaux => Box::new(Tremaux::new(from, to)),
+			Strategy::DeadEndFilling => Box::new(DeadEndFilling::new(map, from, to)),
+		}
+	}
+}
+
+impl fmt::Display for Strategy {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Strategy::LeftHand => "Left-hand wall follower",
+				Strategy::RightHand => "Right-hand wall follower",
+				Strategy::Pledge => "Pledge algorithm",
+				Strategy::Tremaux => "Trémaux's algorithm",
+				Strategy::DeadEndFilling => "Dead-end filling",
+			}
+		)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+	pub steps: usize,
+	pub solved: bool,
+}
+
+/// Steps `agent` until it stops, or until it has taken so many steps that it
+/// must be going in circles.
+pub fn run<A, F>(map: &Map, agent: &mut A, mut peek: F) -> Outcome
+where
+	A: Agent + ?Sized,
+	F: FnMut(&Map, &A),
+{
+	let max_steps = 16 * map.rows * map.columns;
+
+	peek(map, agent);
+	while agent.steps() < max_steps && agent.step(map) {
+		peek(map, agent);
+	}
+
+	Outcome {
+		steps: agent.steps(),
+		solved: agent.is_solved(),
+	}
+}
+
+/// Renders the map with one character per cell, in between the walls, so that
+/// the agents have somewhere to be drawn.
+pub fn frame<F>(map: &Map, mark: F) -> String
+where
+	F: Fn(&Position) -> char,
+{
+	let horizontal = |r: usize, c: usize| c < map.columns && map.has_horizontal_wall(&Position(r, c));
+	let vertical = |r: usize, c: usize| r < map.rows && map.has_vertical_wall(&Position(r, c));
+
+	let mut s = String::with_capacity((2 * map.rows + 1) * (2 * map.columns + 2));
+	for r in 0..=map.rows {
+		for c in 0..=map.columns {
+			let mut junction = WallJunction::default();
+			junction.set_up(r > 0 && vertical(r - 1, c));
+			junction.set_left(c > 0 && horizontal(r, c - 1));
+			junction.set_right(horizontal(r, c));
+			junction.set_down(vertical(r, c));
+			s.push(char::from(junction));
+			if c < map.columns {
+				s.push(if horizontal(r, c) { '─' } else { ' ' });
+			}
+		}
+		s.push('\n');
+
+		if r < map.rows {
+			for c in 0..=map.columns {
+				s.push(if vertical(r, c) { '│' } else { ' ' });
+				if c < map.columns {
+					s.push(mark(&Position(r, c)));
+				}
+			}
+			s.push('\n');
+		}
+	}
+	s
+}
+
+fn is_open(map: &Map, pos: &Position, dir: &Direction) -> bool {
+	map.is(pos, dir) == Some(false)
+}
+
+fn trail_mark(pos: &Position, position: &Position, goal: &Position, visited: &HashSet<Position>) -> char {
+	if pos == position {
+		'@'
+	} else if pos == goal {
+		'X'
+	} else if visited.contains(pos) {
+		'·'
+	} else {
+		' '
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hand {
+	Left,
+	Right,
+}
+
+pub struct WallFollower {
+	position: Position,
+	heading: Direction,
+	goal: Position,
+	hand: Hand,
+	steps: usize,
+	seen: HashSet<(Position, Direction)>,
+	visited: HashSet<Position>,
+}
+
+impl WallFollower {
+	pub fn new(from: Position, to: Position, hand: Hand) -> WallFollower {
+		let mut visited = HashSet::new();
+		visited.insert(from);
+		WallFollower {
+			position: from,
+			heading: Direction::Right,
+			goal: to,
+			hand,
+			steps: 0,
+			seen: HashSet::new(),
+			visited,
+		}
+	}
+}
+
+impl Agent for WallFollower {
+	fn step(&mut self, map: &Map) -> bool {
+		if self.is_solved() || !self.seen.insert((self.position, self.heading)) {
+			return false;
+		}
+
+		let h = self.heading;
+		let order = match self.hand {
+			Hand::Left => [h.turn_left(), h, h.turn_right(), h.opposite()],
+			Hand::Right => [h.turn_right(), h, h.turn_left(), h.opposite()],
+		};
+		if let Some(dir) = order.iter().find(|d| is_open(map, &self.position, d)) {
+			self.heading = *dir;
+			self.position = map.move_in_direction(&self.position, dir).unwrap();
+			self.visited.insert(self.position);
+			self.steps += 1;
+			true
+		} else {
+			false
+		}
+	}
+	fn steps(&self) -> usize {
+		self.steps
+	}
+	fn is_solved(&self) -> bool {
+		self.position == self.goal
+	}
+	fn mark(&self, pos: &Position) -> char {
+		trail_mark(pos, &self.position, &self.goal, &self.visited)
+	}
+}
+
+/// Walks straight in the preferred direction until it hits a wall, then
+/// follows the wall with its left hand until the sum of its turns is zero.
+pub struct Pledge {
+	position: Position,
+	heading: Direction,
+	preferred: Direction,
+	goal: Position,
+	turns: isize,
+	following: bool,
+	steps: usize,
+	seen: HashSet<(Position, Direction, isize, bool)>,
+	visited: HashSet<Position>,
+}
+
+impl Pledge {
+	pub fn new(from: Position, to: Position, preferred: Direction) -> Pledge {
+		let mut visited = HashSet::new();
+		visited.insert(from);
+		Pledge {
+			position: from,
+			heading: preferred,
+			preferred,
+			goal: to,
+			turns: 0,
+			following: false,
+			steps: 0,
+			seen: HashSet::new(),
+			visited,
+		}
+	}
+}
+
+impl Agent for Pledge {
+	fn step(&mut self, map: &Map) -> bool {
+		if self.is_solved()
+			|| !self
+				.seen
+				.insert((self.position, self.heading, self.turns, self.following))
+		{
+			return false;
+		}
+
+		let h = self.heading;
+		let turns = if self.following {
+			[(h.turn_left(), 1), (h, 0), (h.turn_right(), -1), (h.opposite(), -2)]
+		} else {
+			[(h, 0), (h.turn_right(), -1), (h.opposite(), -2), (h.turn_left(), -3)]
+		};
+		if let Some((dir, turn)) = turns.iter().find(|(d, _)| is_open(map, &self.position, d)) {
+			if *turn != 0 {
+				self.following = true;
+			}
+			self.turns += turn;
+			if self.turns == 0 {
+				self.following = false;
+			}
+			self.heading = *dir;
+			self.position = map.move_in_direction(&self.position, dir).unwrap();
+			self.visited.insert(self.position);
+			self.steps += 1;
+			if !self.following {
+				self.heading = self.preferred;
+			}
+			true
+		} else {
+			false
+		}
+	}
+	fn steps(&self) -> usize {
+		self.steps
+	}
+	fn is_solved(&self) -> bool {
+		self.position == self.goal
+	}
+	fn mark(&self, pos: &Position) -> char {
+		trail_mark(pos, &self.position, &self.goal, &self.visited)
+	}
+}
+
+/// Marks every passage each time it's walked, never walks a passage more than
+/// twice, and turns back when a new passage leads to an old junction.
+pub struct Tremaux {
+	position: Position,
+	came_from: Option<Direction>,
+	goal: Position,
+	steps: usize,
+	/// The marks of each passage, kept at both of its ends.
+	marks: HashMap<(Position, Direction), u8>,
+}
+
+impl Tremaux {
+	pub fn new(from: Position, to: Position) -> Tremaux {
+		Tremaux {
+			position: from,
+			came_from: None,
+			goal: to,
+			steps: 0,
+			marks: HashMap::new(),
+		}
+	}
+
+	fn marks_of(&self, pos: &Position, dir: &Direction) -> u8 {
+		*self.marks.get(&(*pos, *dir)).unwrap_or(&0)
+	}
+}
+
+impl Agent for Tremaux {
+	fn step(&mut self, map: &Map) -> bool {
+		if self.is_solved() {
+			return false;
+		}
+
+		let open: Vec<_> = DIRECTIONS
+			.iter()
+			.filter(|d| is_open(map, &self.position, d) && self.marks_of(&self.position, d) < 2)
+			.collect();
+		let back = self.came_from;
+		let is_old = open
+			.iter()
+			.any(|d| Some(**d) != back && self.marks_of(&self.position, d) > 0);
+
+		let next = match back {
+			Some(b) if is_old && self.marks_of(&self.position, &b) == 1 => Some(b),
+			_ => open
+				.iter()
+				.min_by_key(|d| (self.marks_of(&self.position, d), Some(***d) == back))
+				.map(|d| **d),
+		};
+
+		if let Some(dir) = next {
+			let moved = map.move_in_direction(&self.position, &dir).unwrap();
+			for end in &[(self.position, dir), (moved, dir.opposite())] {
+				*self.marks.entry(*end).or_insert(0) += 1;
+			}
+			self.position = moved;
+			self.came_from = Some(dir.opposite());
+			self.steps += 1;
+			true
+		} else {
+			false
+		}
+	}
+	fn steps(&self) -> usize {
+		self.steps
+	}
+	fn is_solved(&self) -> bool {
+		self.position == self.goal
+	}
+	fn mark(&self, pos: &Position) -> char {
+		if pos == &self.position {
+			return '@';
+		} else if pos == &self.goal {
+			return 'X';
+		}
+		// The most marks on any of the passages out of the cell.
+		let marks = DIRECTIONS.iter().map(|d| self.marks_of(pos, d)).max();
+		match marks {
+			Some(0) | None => ' ',
+			Some(1) => '·',
+			Some(_) => '×',
+		}
+	}
+}
+
+/// Not a walker, instead it fills in one dead end at a time until only the
+/// path between start and goal remains.
+pub struct DeadEndFilling {
+	position: Position,
+	start: Position,
+	goal: Position,
+	steps: usize,
+	filled: HashSet<Position>,
+	dead_ends: Vec<Position>,
+}
+
+impl DeadEndFilling {
+	pub fn new(map: &Map, from: Position, to: Position) -> DeadEndFilling {
+		let mut filler = DeadEndFilling {
+			position: from,
+			start: from,
+			goal: to,
+			steps: 0,
+			filled: HashSet::new(),
+			dead_ends: Vec::new(),
+		};
+		filler.dead_ends = map.cells().filter(|p| filler.is_dead_end(map, p)).collect();
+		filler
+	}
+
+	fn open_neighbours(&self, map: &Map, pos: &Position) -> Vec<Position> {
+		map.neighbours(pos)
+			.filter(|(d, _)| is_open(map, pos, d))
+			.map(|(_, p)| p)
+			.filter(|p| !self.filled.contains(p))
+			.collect()
+	}
+
+	fn is_dead_end(&self, map: &Map, pos: &Position) -> bool {
+		pos != &self.start
+			&& pos != &self.goal
+			&& !self.filled.contains(pos)
+			&& self.open_neighbours(map, pos).len() <= 1
+	}
+
+	fn is_connected(&self, map: &Map) -> bool {
+		let mut visited = HashSet::new();
+		visited.insert(self.start);
+		let mut to_visit = vec![self.start];
+		while let Some(next) = to_visit.pop() {
+			if next == self.goal {
+				return true;
+			}
+			for moved in self.open_neighbours(map, &next) {
+				if visited.insert(moved) {
+					to_visit.push(moved);
+				}
+			}
+		}
+		false
+	}
+}
+
+impl Agent for DeadEndFilling {
+	fn step(&mut self, map: &Map) -> bool {
+		while let Some(next) = self.dead_ends.pop() {
+			if self.is_dead_end(map, &next) {
+				let neighbours = self.open_neighbours(map, &next);
+				self.filled.insert(next);
+				self.position = next;
+				self.steps += 1;
+				for neighbour in neighbours {
+					if self.is_dead_end(map, &neighbour) {
+						self.dead_ends.push(neighbour);
+					}
+				}
+				return true;
+			}
+		}
+
+		self.position = if self.is_connected(map) { self.goal } else { self.start };
+		false
+	}
+	fn steps(&self) -> usize {
+		self.steps
+	}
+	fn is_solved(&self) -> bool {
+		self.position == self.goal
+	}
+	fn mark(&self, pos: &Position) -> char {
+		if pos == &self.start {
+			'@'
+		} else if pos == &self.goal {
+			'X'
+		} else if self.filled.contains(pos) {
+			'█'
+		} else {
+			' '
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use crate::map::ALGORITHMS;
+
+	#[test]
+	fn every_agent_solves_perfect_mazes() {
+		for algorithm in ALGORITHMS.iter() {
+			for (rows, columns) in &[(1, 1), (1, 6), (5, 5), (8, 11)] {
+				for seed in 0..5 {
+					let mut rng = StdRng::seed_from_u64(seed);
+					let map = Map::generate(
+						*algorithm,
+						*rows,
+						*columns,
+						Position(0, 0),
+						&mut rng,
+						|_| {},
+						|_, _, _| {},
+					);
+					let (start, goal) = map.ends();
+					for strategy in STRATEGIES.iter() {
+						let mut agent = strategy.agent(&map, start, goal);
+						let outcome = run(&map, agent.as_mut(), |_, _| {});
+						assert!(
+							outcome.solved,
+							"{} on {} {}×{} with seed {}",
+							strategy,
+							algorithm.name(),
+							rows,
+							columns,
+							seed
+						);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn wall_followers_fail_around_loops() {
+		// Without any inner walls, following the outer wall goes around the
+		// middle cell without ever reaching it.
+		let mut map = Map::new(3, 3);
+		for pos in map.cells().collect::<Vec<_>>() {
+			for (dir, _) in map.neighbours(&pos).collect::<Vec<_>>() {
+				map.set(&pos, &dir, false);
+			}
+		}
+		for strategy in &[Strategy::LeftHand, Strategy::RightHand] {
+			let mut agent = strategy.agent(&map, Position(0, 0), Position(1, 1));
+			let outcome = run(&map, agent.as_mut(), |_, _| {});
+			assert!(!outcome.solved, "{}", strategy);
+			assert!(outcome.steps < 16 * 9, "{} didn't notice it was going around", strategy);
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::grid::{Shape, Side};
+use crate::map::{Direction, Map, Position};
+
+/// The cells of a [`Map`] as a [`Shape`], so that square mazes are generated
+/// the same way as mazes on the other grids. Cells are numbered row by row,
+/// without the ones the mask leaves out.
+pub struct Square {
+	/// The map the cells are from, for which cells are next to each other.
+	map: Map,
+	positions: Vec<Position>,
+	cells: HashMap<Position, usize>,
+}
+
+impl Square {
+	pub fn new(map: &Map) -> Square {
+		let positions: Vec<_> = map.cells().collect();
+		let cells = positions.iter().enumerate().map(|(i, pos)| (*pos, i)).collect();
+		Square {
+			map: map.clone(),
+			positions,
+			cells,
+		}
+	}
+
+	pub fn position(&self, cell: usize) -> Position {
+		self.positions[cell]
+	}
+
+	/// The direction from a cell to one next to it.
+	pub fn direction(&self, from: usize, to: usize) -> Direction {
+		let to = self.position(to);
+		self.map
+			.neighbours(&self.position(from))
+			.find(|(_, other)| *other == to)
+			.map(|(dir, _)| dir)
+			.unwrap()
+	}
+
+	fn move_in_direction(&self, cell: usize, dir: &Direction) -> Option<usize> {
+		self.map
+			.move_in_direction(&self.position(cell), dir)
+			.map(|pos| self.cells[&pos])
+	}
+}
+
+impl Shape for Square {
+	fn count(&self) -> usize {
+		self.positions.len()
+	}
+
+	fn sides(&self, cell: usize) -> Vec<(Side, Option<usize>)> {
+		let Position(r, c) = self.position(cell);
+		let (x, y) = (c as f64, r as f64);
+		let corners = [(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)];
+		[Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+			.iter()
+			.enumerate()
+			.map(|(i, dir)| {
+				(
+					Side::Line(corners[i], corners[(i + 1) % 4]),
+					self.move_in_direction(cell, dir),
+				)
+			})
+			.collect()
+	}
+
+	/// In the order of [`crate::map::DIRECTIONS`], rather than going around the
+	/// cell.
+	fn neighbours(&self, cell: usize) -> Vec<usize> {
+		self.map
+			.neighbours(&self.position(cell))
+			.map(|(_, pos)| self.cells[&pos])
+			.collect()
+	}
+
+	fn center(&self, cell: usize) -> (f64, f64) {
+		let Position(r, c) = self.position(cell);
+		(c as f64 + 0.5, r as f64 + 0.5)
+	}
+
+	fn size(&self) -> (f64, f64) {
+		(self.map.columns as f64, self.map.rows as f64)
+	}
+
+	fn cell(&self, pos: &Position) -> Option<usize> {
+		self.cells.get(pos).copied()
+	}
+
+	fn arrow(&self, from: usize, to: usize) -> char {
+		self.direction(from, to).to_string().chars().next().unwrap()
+	}
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+
+use crate::map::{Map, Position};
+
+/// Metrics describing the shape of a maze, see [`Map::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+	pub cells: usize,
+	/// Cells with exactly one opening.
+	pub dead_ends: usize,
+	/// Cells with three or more openings.
+	pub junctions: usize,
+	/// Cells with two openings on opposite sides.
+	pub straights: usize,
+	/// The average number of ways on from a cell that isn't a dead end, when
+	/// entering it from one of its neighbours.
+	pub branching_factor: f64,
+	/// Passages connecting two dead ends or junctions.
+	pub corridors: usize,
+	pub average_corridor_length: f64,
+	pub max_corridor_length: usize,
+	/// The average number of cells off the solution per dead end. Mazes with
+	/// few but long dead ends "flow" like a river and score high, mazes with
+	/// many short dead ends score low.
+	pub river_factor: f64,
+	pub solution: Option<SolutionStats>,
+}
+
+/// Metrics describing the path from the upper left to the lower right cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionStats {
+	/// Number of steps.
+	pub length: usize,
+	/// The share of all cells that are on the path.
+	pub coverage: f64,
+	pub turns: usize,
+	/// Turns per step.
+	pub average_turns: f64,
+	/// Cells on the path where there is more than one way forward.
+	pub decision_points: usize,
+}
+
+impl Stats {
+	pub fn dead_end_percentage(&self) -> f64 {
+		ratio(self.dead_ends, self.cells) * 100.0
+	}
+
+	pub fn to_json(&self) -> String {
+		let solution = match &self.solution {
+			Some(s) => format!(
+				"{{\"length\":{},\"coverage\":{},\"turns\":{},\"average_turns\":{},\"decision_points\":{}}}",
+				s.length, s.coverage, s.turns, s.average_turns, s.decision_points
+			),
+			None => "null".to_string(),
+		};
+		format!(
+			"{{\"cells\":{},\"dead_ends\":{},\"dead_end_percentage\":{},\"junctions\":{},\"straights\":{},\"branching_factor\":{},\"corridors\":{},\"average_corridor_length\":{},\"max_corridor_length\":{},\"river_factor\":{},\"solution\":{}}}",
+			self.cells,
+			self.dead_ends,
+			self.dead_end_percentage(),
+			self.junctions,
+			self.straights,
+			self.branching_factor,
+			self.corridors,
+			self.average_corridor_length,
+			self.max_corridor_length,
+			self.river_factor,
+			solution
+		)
+	}
+}
+
+impl fmt::Display for Stats {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(
+			f,
+			"Dead ends:               {} ({:.1}%)",
+			self.dead_ends,
+			self.dead_end_percentage()
+		)?;
+		writeln!(f, "Junctions:               {}", self.junctions)?;
+		writeln!(f, "Straight corridors:      {}", self.straights)?;
+		writeln!(f, "Branching factor:        {:.2}", self.branching_factor)?;
+		writeln!(f, "Corridors:               {}", self.corridors)?;
+		writeln!(f, "Average corridor length: {:.2}", self.average_corridor_length)?;
+		writeln!(f, "Max corridor length:     {}", self.max_corridor_length)?;
+		write!(f, "River factor:            {:.2}", self.river_factor)?;
+		if let Some(s) = &self.solution {
+			writeln!(f)?;
+			writeln!(f, "Solution length:         {}", s.length)?;
+			writeln!(f, "Solution coverage:       {:.1}%", s.coverage * 100.0)?;
+			writeln!(
+				f,
+				"Solution turns:          {} ({:.2} per step)",
+				s.turns, s.average_turns
+			)?;
+			write!(f, "Decision points:         {}", s.decision_points)?;
+		}
+		Ok(())
+	}
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+	if b == 0 {
+		0.0
+	} else {
+		a as f64 / b as f64
+	}
+}
+
+impl Map {
+	pub fn stats(&self) -> Stats {
+		let positions: Vec<_> = self.cells().collect();
+		let degree = |pos: &Position| self.openings(pos).len();
+
+		let mut dead_ends = 0;
+		let mut junctions = 0;
+		let mut straights = 0;
+		let mut passages = 0;
+		let mut ways_on = 0;
+		for pos in &positions {
+			let openings = self.openings(pos);
+			if openings.len() > 1 {
+				passages += 1;
+				ways_on += openings.len() - 1;
+			}
+			match openings.len() {
+				1 => dead_ends += 1,
+				2 if openings[0].opposite() == openings[1] => straights += 1,
+				n if n > 2 => junctions += 1,
+				_ => {}
+			}
+		}
+
+		// Walks every corridor from both ends, so everything is counted twice.
+		let mut corridor_ends = 0;
+		let mut total_length = 0;
+		let mut max_corridor_length = 0;
+		for pos in positions.iter().filter(|p| degree(p) != 2) {
+			for dir in self.openings(pos) {
+				let mut current = self.move_in_direction(pos, &dir).unwrap();
+				let mut heading = dir;
+				let mut length = 1;
+				while current != *pos && degree(&current) == 2 {
+					heading = *self
+						.openings(&current)
+						.iter()
+						.find(|d| **d != heading.opposite())
+						.unwrap();
+					current = self.move_in_direction(&current, &heading).unwrap();
+					length += 1;
+				}
+				corridor_ends += 1;
+				total_length += length;
+				max_corridor_length = max_corridor_length.max(length);
+			}
+		}
+
+		let (start, goal) = self.ends();
+		let solution = self.solve(start, goal).map(|path| {
+			let mut current = start;
+			let mut decision_points = 0;
+			for (i, dir) in path.iter().enumerate() {
+				let ways_forward = degree(&current) - if i == 0 { 0 } else { 1 };
+				if ways_forward > 1 {
+					decision_points += 1;
+				}
+				current = self.move_in_direction(&current, dir).unwrap();
+			}
+			let turns = path.windows(2).filter(|w| w[0] != w[1]).count();
+			SolutionStats {
+				length: path.len(),
+				coverage: ratio(path.len() + 1, positions.len()),
+				turns,
+				average_turns: ratio(turns, path.len()),
+				decision_points,
+			}
+		});
+
+		let off_solution = positions.len() - solution.as_ref().map(|s| s.length + 1).unwrap_or(0);
+
+		Stats {
+			cells: positions.len(),
+			dead_ends,
+			junctions,
+			straights,
+			branching_factor: ratio(ways_on, passages),
+			corridors: corridor_ends / 2,
+			average_corridor_length: ratio(total_length, corridor_ends),
+			max_corridor_length,
+			river_factor: ratio(off_solution, dead_ends),
+			solution,
+		}
+	}
+}
@@ -0,0 +1,120 @@
+use std::fmt::Write;
+
+use crate::draw::DrawOptions;
+use crate::map::{Map, Position};
+
+pub fn render(map: &Map, start: Position, goal: Position, options: &DrawOptions) -> String {
+	let mut svg = String::new();
+	writeln!(
+		svg,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+		options.width(map),
+		options.height(map)
+	)
+	.unwrap();
+	write_layers(map, start, goal, options, &mut svg);
+	svg.push_str("</svg>\n");
+	svg
+}
+
+/// Writes the elements of the maze without the surrounding `<svg>` element,
+/// for embedding it in other documents.
+pub fn write_layers(map: &Map, start: Position, goal: Position, options: &DrawOptions, svg: &mut String) {
+	let cell = options.cell_size;
+
+	writeln!(
+		svg,
+		r#"<rect width="{}" height="{}" fill="{}"/>"#,
+		options.width(map),
+		options.height(map),
+		options.background
+	)
+	.unwrap();
+
+	if options.heatmap {
+		let distances = map.distances(start);
+		let max = distances.values().copied().max().unwrap_or(0).max(1) as f64;
+		svg.push_str(&format!(r#"<g fill="{}">"#, options.heatmap_color));
+		svg.push('\n');
+		for (pos, distance) in map.cells().filter_map(|p| distances.get(&p).map(|d| (p, d))) {
+			writeln!(
+				svg,
+				r#"<rect x="{}" y="{}" width="{}" height="{}" fill-opacity="{:.3}"/>"#,
+				options.x(pos.1),
+				options.y(pos.0),
+				cell,
+				cell,
+				*distance as f64 / max
+			)
+			.unwrap();
+		}
+		svg.push_str("</g>\n");
+	}
+
+	if options.solution {
+		if let Some(path) = solution_path(map, start, goal, options) {
+			writeln!(
+				svg,
+				r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+				path,
+				options.solution_color,
+				cell / 4.0
+			)
+			.unwrap();
+		}
+	}
+
+	if options.markers {
+		for (pos, color) in &[(start, &options.start_color), (goal, &options.goal_color)] {
+			writeln!(
+				svg,
+				r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+				options.x(pos.1) + cell / 2.0,
+				options.y(pos.0) + cell / 2.0,
+				cell * 0.3,
+				color
+			)
+			.unwrap();
+		}
+	}
+
+	writeln!(
+		svg,
+		r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+		map.wall_runs()
+			.iter()
+			.map(|(from, to)| format!(
+				"M{} {}L{} {}",
+				options.x(from.1),
+				options.y(from.0),
+				options.x(to.1),
+				options.y(to.0)
+			))
+			.collect::<String>(),
+		options.wall_color,
+		options.wall_thickness
+	)
+	.unwrap();
+}
+
+/// The solution as the data of a `<path>` through the middle of the cells,
+/// with a line of its own after every edge that wraps around.
+pub fn solution_path(map: &Map, start: Position, goal: Position, options: &DrawOptions) -> Option<String> {
+	let cell = options.cell_size;
+	let lines = map.solution_lines(start, goal)?;
+	Some(
+		lines
+			.iter()
+			.flat_map(|line| {
+				line.iter().enumerate().map(move |(i, (x, y))| {
+					format!(
+						"{}{},{}",
+						if i == 0 { "M" } else { "L" },
+						options.margin + x * cell,
+						options.margin + y * cell
+					)
+				})
+			})
+			.collect(),
+	)
+}
@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+use crate::draw::DrawOptions;
+use crate::map::{Map, Position};
+use crate::raster;
+
+/// What to write in each cell of a TikZ picture.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Labels {
+	/// The row and column of the cell.
+	Position,
+	/// The number of steps to the cell from the start.
+	Distance,
+}
+
+impl Labels {
+	pub fn from_name(name: &str) -> Option<Labels> {
+		match name {
+			"position" => Some(Labels::Position),
+			"distance" => Some(Labels::Distance),
+			_ => None,
+		}
+	}
+}
+
+/// Draws the maze as a `tikzpicture`, to be included in a LaTeX document that
+/// loads TikZ. Lengths are in points, and labels are set in the document's
+/// own font. The background and margin are left to the document.
+pub fn render(
+	map: &Map,
+	start: Position,
+	goal: Position,
+	options: &DrawOptions,
+	labels: Option<Labels>,
+) -> Result<String, String> {
+	let mut tikz = String::from("% maze_generator\n");
+	// One unit is one cell, with the first row at the top.
+	writeln!(tikz, "\\begin{{tikzpicture}}[x={0}pt, y=-{0}pt]", options.cell_size).unwrap();
+	for (name, color) in &[
+		("mazewall", &options.wall_color),
+		("mazesolution", &options.solution_color),
+		("mazestart", &options.start_color),
+		("mazegoal", &options.goal_color),
+		("mazeheatmap", &options.heatmap_color),
+	] {
+		let [r, g, b] = raster::color_of(color)?;
+		writeln!(tikz, "\\definecolor{{{}}}{{RGB}}{{{},{},{}}}", name, r, g, b).unwrap();
+	}
+
+	let distances = map.distances(start);
+	if options.heatmap {
+		let max = distances.values().copied().max().unwrap_or(0).max(1) as f64;
+		for (pos, distance) in map.cells().filter_map(|p| distances.get(&p).map(|d| (p, d))) {
+			writeln!(
+				tikz,
+				"\\fill[mazeheatmap, opacity={:.3}] ({}, {}) rectangle +(1, 1);",
+				*distance as f64 / max,
+				pos.1,
+				pos.0
+			)
+			.unwrap();
+		}
+	}
+
+	if options.solution {
+		if let Some(lines) = map.solution_lines(start, goal) {
+			writeln!(
+				tikz,
+				"\\draw[mazesolution, line width={}pt, line cap=round, line join=round] {};",
+				options.cell_size / 4.0,
+				lines
+					.iter()
+					.map(|line| line
+						.iter()
+						.map(|(x, y)| format!("({}, {})", x, y))
+						.collect::<Vec<_>>()
+						.join(" -- "))
+					.collect::<Vec<_>>()
+					.join(" ")
+			)
+			.unwrap();
+		}
+	}
+
+	if options.markers {
+		for (pos, color) in &[(start, "mazestart"), (goal, "mazegoal")] {
+			writeln!(tikz, "\\fill[{}] ({}.5, {}.5) circle[radius=0.3];", color, pos.1, pos.0).unwrap();
+		}
+	}
+
+	if let Some(labels) = labels {
+		for pos in map.cells() {
+			let label = match labels {
+				Labels::Position => format!("{},{}", pos.0, pos.1),
+				Labels::Distance => match distances.get(&pos) {
+					Some(distance) => distance.to_string(),
+					None => continue,
+				},
+			};
+			writeln!(
+				tikz,
+				"\\node[font=\\footnotesize] at ({}.5, {}.5) {{{}}};",
+				pos.1, pos.0, label
+			)
+			.unwrap();
+		}
+	}
+
+	writeln!(
+		tikz,
+		"\\draw[mazewall, line width={}pt, line cap=rect]{};",
+		options.wall_thickness,
+		map.wall_runs()
+			.iter()
+			.map(|(from, to)| format!(" ({}, {}) -- ({}, {})", from.1, from.0, to.1, to.0))
+			.collect::<String>()
+	)
+	.unwrap();
+	tikz.push_str("\\end{tikzpicture}\n");
+	Ok(tikz)
+}
@@ -0,0 +1,253 @@
+use std::fmt::Write;
+
+use crate::draw::DrawOptions;
+use crate::map::{Map, Position};
+use crate::raster::{self, Image};
+
+/// The Tiled version the maps are written for.
+const VERSION: &str = "1.10";
+
+/// The tileset has a tile for every junction, in a 4 by 4 grid. The tile's id
+/// is the junction's bits, so tile 0 is an open floor.
+const TILES: usize = 16;
+const TILESET_COLUMNS: usize = 4;
+
+/// Walls between two cells go from one corner to the next, so they're the
+/// same tiles as straight corners.
+const HORIZONTAL: usize = 0b0110;
+const VERTICAL: usize = 0b1001;
+
+/// The maze on a grid of tiles, with a tile for every cell, every wall between
+/// cells and every corner.
+struct Layout {
+	width: usize,
+	height: usize,
+	/// The tile ids, row by row.
+	tiles: Vec<usize>,
+	/// Layers of objects, each covering a cell.
+	objects: Vec<(&'static str, Vec<Position>)>,
+}
+
+fn layout(map: &Map, start: Position, goal: Position) -> Layout {
+	let (width, height) = (2 * map.columns + 1, 2 * map.rows + 1);
+	let junctions = map.junctions();
+	let mut tiles = vec![0; width * height];
+	for (y, row) in tiles.chunks_mut(width).enumerate() {
+		for (x, tile) in row.iter_mut().enumerate() {
+			let (r, c) = (y / 2, x / 2);
+			*tile = match (x % 2, y % 2) {
+				(0, 0) => junctions[r][c].bits(),
+				(1, 0) if map.has_horizontal_wall(&Position(r, c)) => HORIZONTAL,
+				(0, 1) if map.has_vertical_wall(&Position(r, c)) => VERTICAL,
+				_ => 0,
+			};
+		}
+	}
+
+	let dead_ends = map
+		.cells()
+		.filter(|pos| *pos != start && *pos != goal && map.openings(pos).len() == 1)
+		.collect();
+	Layout {
+		width,
+		height,
+		tiles,
+		objects: vec![("start", vec![start]), ("goal", vec![goal]), ("dead ends", dead_ends)],
+	}
+}
+
+/// The tileset image, with every junction drawn with the walls and background
+/// of `options`, on tiles as large as a cell.
+pub fn tileset(options: &DrawOptions) -> Result<Image, String> {
+	let size = tile_size(options);
+	let rows = TILES / TILESET_COLUMNS;
+	let mut image = Image::new(
+		size * TILESET_COLUMNS,
+		size * rows,
+		raster::color_of(&options.background)?,
+	);
+	let color = raster::color_of(&options.wall_color)?;
+	let half = options.wall_thickness / 2.0;
+	for bits in 1..TILES {
+		let left = (bits % TILESET_COLUMNS * size) as f64;
+		let top = (bits / TILESET_COLUMNS * size) as f64;
+		let (cx, cy) = (left + size as f64 / 2.0, top + size as f64 / 2.0);
+		let (right, bottom) = (left + size as f64, top + size as f64);
+		image.fill_rect(cx - half, cy - half, cx + half, cy + half, color);
+		for (bit, (x0, y0, x1, y1)) in &[
+			(0b1000, (cx - half, top, cx + half, cy)),
+			(0b0100, (left, cy - half, cx, cy + half)),
+			(0b0010, (cx, cy - half, right, cy + half)),
+			(0b0001, (cx - half, cy, cx + half, bottom)),
+		] {
+			if bits & bit != 0 {
+				image.fill_rect(*x0, *y0, *x1, *y1, color);
+			}
+		}
+	}
+	Ok(image)
+}
+
+fn tile_size(options: &DrawOptions) -> usize {
+	(options.cell_size.round() as usize).max(1)
+}
+
+/// A Tiled map in the XML format, with the tileset image at `image`, relative
+/// to the map. Walls are on the `walls` layer, and the start, goal and every
+/// other dead end are objects on layers of their own.
+pub fn tmx(map: &Map, start: Position, goal: Position, options: &DrawOptions, image: &str) -> String {
+	let layout = layout(map, start, goal);
+	let size = tile_size(options);
+	let mut tmx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	writeln!(
+		tmx,
+		r#"<map version="{}" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="{}" nextobjectid="{}">"#,
+		VERSION,
+		layout.width,
+		layout.height,
+		size,
+		size,
+		layout.objects.len() + 2,
+		layout.objects.iter().map(|(_, o)| o.len()).sum::<usize>() + 1
+	)
+	.unwrap();
+	writeln!(
+		tmx,
+		r#" <tileset firstgid="1" name="maze" tilewidth="{0}" tileheight="{0}" tilecount="{1}" columns="{2}">"#,
+		size, TILES, TILESET_COLUMNS
+	)
+	.unwrap();
+	writeln!(
+		tmx,
+		r#"  <image source="{}" width="{}" height="{}"/>"#,
+		xml_escape(image),
+		size * TILESET_COLUMNS,
+		size * TILES / TILESET_COLUMNS
+	)
+	.unwrap();
+	for id in 1..TILES {
+		writeln!(
+			tmx,
+			r#"  <tile id="{}"><properties><property name="wall" type="bool" value="true"/></properties></tile>"#,
+			id
+		)
+		.unwrap();
+	}
+	tmx.push_str(" </tileset>\n");
+
+	writeln!(
+		tmx,
+		r#" <layer id="1" name="walls" width="{}" height="{}">"#,
+		layout.width, layout.height
+	)
+	.unwrap();
+	tmx.push_str("  <data encoding=\"csv\">\n");
+	let rows: Vec<String> = layout
+		.tiles
+		.chunks(layout.width)
+		.map(|row| row.iter().map(|t| (t + 1).to_string()).collect::<Vec<_>>().join(","))
+		.collect();
+	tmx.push_str(&rows.join(",\n"));
+	tmx.push_str("\n  </data>\n </layer>\n");
+
+	let mut id = 1;
+	for (layer, (name, positions)) in layout.objects.iter().enumerate() {
+		writeln!(tmx, r#" <objectgroup id="{}" name="{}">"#, layer + 2, name).unwrap();
+		for pos in positions {
+			writeln!(
+				tmx,
+				r#"  <object id="{}" name="{}" x="{}" y="{}" width="{}" height="{}"/>"#,
+				id,
+				name,
+				(2 * pos.1 + 1) * size,
+				(2 * pos.0 + 1) * size,
+				size,
+				size
+			)
+			.unwrap();
+			id += 1;
+		}
+		tmx.push_str(" </objectgroup>\n");
+	}
+	tmx.push_str("</map>\n");
+	tmx
+}
+
+/// The same map as [`tmx`], in Tiled's JSON format.
+pub fn tmj(map: &Map, start: Position, goal: Position, options: &DrawOptions, image: &str) -> String {
+	let layout = layout(map, start, goal);
+	let size = tile_size(options);
+	let tiles: Vec<String> = (1..TILES)
+		.map(|id| {
+			format!(
+				r#"{{"id":{},"properties":[{{"name":"wall","type":"bool","value":true}}]}}"#,
+				id
+			)
+		})
+		.collect();
+	let tileset = format!(
+		r#"{{"firstgid":1,"name":"maze","tilewidth":{0},"tileheight":{0},"tilecount":{1},"columns":{2},"margin":0,"spacing":0,"image":"{3}","imagewidth":{4},"imageheight":{5},"tiles":[{6}]}}"#,
+		size,
+		TILES,
+		TILESET_COLUMNS,
+		json_escape(image),
+		size * TILESET_COLUMNS,
+		size * TILES / TILESET_COLUMNS,
+		tiles.join(",")
+	);
+
+	let mut layers = vec![format!(
+		r#"{{"id":1,"name":"walls","type":"tilelayer","x":0,"y":0,"width":{},"height":{},"opacity":1,"visible":true,"data":[{}]}}"#,
+		layout.width,
+		layout.height,
+		layout
+			.tiles
+			.iter()
+			.map(|t| (t + 1).to_string())
+			.collect::<Vec<_>>()
+			.join(",")
+	)];
+	let mut id = 1;
+	for (layer, (name, positions)) in layout.objects.iter().enumerate() {
+		let mut objects = Vec::new();
+		for pos in positions {
+			objects.push(format!(
+				r#"{{"id":{},"name":"{}","type":"","x":{},"y":{},"width":{},"height":{},"rotation":0,"visible":true}}"#,
+				id,
+				name,
+				(2 * pos.1 + 1) * size,
+				(2 * pos.0 + 1) * size,
+				size,
+				size
+			));
+			id += 1;
+		}
+		layers.push(format!(
+			r#"{{"id":{},"name":"{}","type":"objectgroup","draworder":"topdown","x":0,"y":0,"opacity":1,"visible":true,"objects":[{}]}}"#,
+			layer + 2,
+			name,
+			objects.join(",")
+		));
+	}
+
+	format!(
+		r#"{{"type":"map","version":"{}","orientation":"orthogonal","renderorder":"right-down","width":{},"height":{},"tilewidth":{},"tileheight":{},"infinite":false,"nextlayerid":{},"nextobjectid":{},"tilesets":[{}],"layers":[{}]}}"#,
+		VERSION,
+		layout.width,
+		layout.height,
+		size,
+		size,
+		layout.objects.len() + 2,
+		id,
+		tileset,
+		layers.join(",")
+	) + "\n"
+}
+
+fn xml_escape(s: &str) -> String {
+	s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+fn json_escape(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,329 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::map::{Algorithm, Map, Position, ALGORITHMS};
+
+/// The walls of a grid in a fixed order, every wall below a cell followed by
+/// every wall to the right of a cell.
+fn walls(rows: usize, columns: usize) -> Vec<(Position, Position)> {
+	let below = (0..rows - 1).flat_map(|r| (0..columns).map(move |c| (Position(r, c), Position(r + 1, c))));
+	let right = (0..rows).flat_map(|r| (0..columns - 1).map(move |c| (Position(r, c), Position(r, c + 1))));
+	below.chain(right).collect()
+}
+
+/// Encodes the open walls of `map` as a bit set, in the order of [`walls`].
+fn key_of(map: &Map) -> u64 {
+	walls(map.rows, map.columns)
+		.iter()
+		.enumerate()
+		.filter(
+			|(_, (a, b))| {
+				if a.0 == b.0 {
+					!map.is_right(a)
+				} else {
+					!map.is_below(a)
+				}
+			},
+		)
+		.fold(0, |key, (i, _)| key | 1 << i)
+}
+
+/// The most cells a grid can have for its spanning trees to be listed, a 4×4
+/// grid already has 100352 of them.
+const MAX_CELLS: usize = 16;
+
+/// Lists every spanning tree of a `rows`×`columns` grid, each encoded as the
+/// bit set of its open walls.
+pub fn spanning_trees(rows: usize, columns: usize) -> Result<Vec<u64>, String> {
+	if rows == 0 || columns == 0 {
+		return Err(String::from("The grid must have at least one row and one column"));
+	}
+	if rows * columns > MAX_CELLS {
+		return Err(format!(
+			"The grid can have at most {} cells, larger grids have too many spanning trees to list",
+			MAX_CELLS
+		));
+	}
+
+	fn find(parents: &mut [usize], i: usize) -> usize {
+		if parents[i] != i {
+			parents[i] = find(parents, parents[i]);
+		}
+		parents[i]
+	}
+	fn recurse(
+		edges: &[(usize, usize)],
+		index: usize,
+		remaining: usize,
+		parents: &[usize],
+		key: u64,
+		trees: &mut Vec<u64>,
+	) {
+		if remaining == 0 {
+			trees.push(key);
+			return;
+		}
+		if edges.len() - index < remaining {
+			return;
+		}
+
+		let (a, b) = edges[index];
+		let mut with = parents.to_vec();
+		let (root_a, root_b) = (find(&mut with, a), find(&mut with, b));
+		if root_a != root_b {
+			with[root_a] = root_b;
+			recurse(edges, index + 1, remaining - 1, &with, key | 1 << index, trees);
+		}
+		recurse(edges, index + 1, remaining, parents, key, trees);
+	}
+
+	let edges: Vec<_> = walls(rows, columns)
+		.iter()
+		.map(|(a, b)| (a.0 * columns + a.1, b.0 * columns + b.1))
+		.collect();
+
+	let cells = rows * columns;
+	let parents: Vec<_> = (0..cells).collect();
+	let mut trees = Vec::new();
+	recurse(&edges, 0, cells - 1, &parents, 0, &mut trees);
+	Ok(trees)
+}
+
+/// Whether an algorithm is supposed to pick every spanning tree with the same
+/// probability.
+pub fn is_uniform(algorithm: &Algorithm) -> bool {
+	matches!(algorithm, Algorithm::AldousBroder | Algorithm::Wilson)
+}
+
+pub struct Uniformity {
+	pub algorithm: Algorithm,
+	/// Number of distinct spanning trees that were generated.
+	pub observed: usize,
+	/// Number of generated mazes that weren't spanning trees at all.
+	pub invalid: usize,
+	pub chi_squared: f64,
+	pub p_value: f64,
+}
+
+pub struct Report {
+	pub rows: usize,
+	pub columns: usize,
+	pub samples: usize,
+	pub seed: u64,
+	pub trees: usize,
+	/// The p-value under which a distribution is considered not uniform.
+	pub significance: f64,
+	pub results: Vec<Uniformity>,
+}
+
+impl Report {
+	/// Whether every algorithm only generated spanning trees, and the
+	/// uniform ones passed the test.
+	pub fn passed(&self) -> bool {
+		self.results
+			.iter()
+			.all(|r| r.invalid == 0 && (!is_uniform(&r.algorithm) || r.p_value >= self.significance))
+	}
+}
+
+/// Samples every algorithm `samples` times on a small grid and runs a
+/// chi-squared test of the generated trees against a uniform distribution
+/// over all spanning trees of the grid.
+pub fn test(rows: usize, columns: usize, samples: usize, seed: u64) -> Result<Report, String> {
+	let trees = spanning_trees(rows, columns)?;
+	let indices: HashMap<_, _> = trees.iter().enumerate().map(|(i, k)| (*k, i)).collect();
+	let expected = samples as f64 / trees.len() as f64;
+
+	let results = ALGORITHMS
+		.iter()
+		.map(|algorithm| {
+			let mut counts = vec![0usize; trees.len()];
+			let mut invalid = 0;
+			for i in 0..samples {
+				let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+				let map = Map::generate(
+					*algorithm,
+					rows,
+					columns,
+					Position(0, 0),
+					&mut rng,
+					|_| {},
+					|_, _, _| {},
+				);
+				match indices.get(&key_of(&map)) {
+					Some(i) => counts[*i] += 1,
+					None => invalid += 1,
+				}
+			}
+
+			let chi_squared = counts
+				.iter()
+				.map(|o| (*o as f64 - expected) * (*o as f64 - expected) / expected)
+				.sum::<f64>();
+			Uniformity {
+				algorithm: *algorithm,
+				observed: counts.iter().filter(|c| **c > 0).count(),
+				invalid,
+				chi_squared,
+				p_value: chi_squared_p_value(chi_squared, trees.len() - 1),
+			}
+		})
+		.collect();
+
+	Ok(Report {
+		rows,
+		columns,
+		samples,
+		seed,
+		trees: trees.len(),
+		significance: 0.001,
+		results,
+	})
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(
+			f,
+			"{}×{} grid, {} spanning trees, {} samples per algorithm, seed {}",
+			self.rows, self.columns, self.trees, self.samples, self.seed
+		)?;
+		writeln!(f)?;
+		write!(
+			f,
+			"{:<10}{:>10}{:>10}{:>14}{:>12}{:>10}{:>14}",
+			"algorithm", "observed", "invalid", "chi-squared", "p-value", "expected", "result"
+		)?;
+		for r in &self.results {
+			let uniform = r.p_value >= self.significance;
+			writeln!(f)?;
+			write!(
+				f,
+				"{:<10}{:>10}{:>10}{:>14.2}{:>12.4}{:>10}{:>14}",
+				r.algorithm.name(),
+				r.observed,
+				r.invalid,
+				r.chi_squared,
+				r.p_value,
+				if is_uniform(&r.algorithm) { "uniform" } else { "biased" },
+				if r.invalid > 0 {
+					"INVALID"
+				} else if uniform {
+					"uniform"
+				} else {
+					"not uniform"
+				}
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// The probability of a chi-squared value at least this large with `df`
+/// degrees of freedom.
+fn chi_squared_p_value(chi_squared: f64, df: usize) -> f64 {
+	if df == 0 {
+		return 1.0;
+	}
+	upper_regularized_gamma(df as f64 / 2.0, chi_squared / 2.0)
+}
+
+/// Q(a, x), computed with a series for small `x` and a continued fraction
+/// otherwise, as described in Numerical Recipes.
+fn upper_regularized_gamma(a: f64, x: f64) -> f64 {
+	const EPSILON: f64 = 1e-14;
+	const MAX_ITERATIONS: usize = 10_000;
+
+	if x <= 0.0 {
+		return 1.0;
+	}
+	let ln_prefix = a * x.ln() - x - ln_gamma(a);
+
+	if x < a + 1.0 {
+		let mut term = 1.0 / a;
+		let mut sum = term;
+		let mut n = a;
+		for _ in 0..MAX_ITERATIONS {
+			n += 1.0;
+			term *= x / n;
+			sum += term;
+			if term.abs() < sum.abs() * EPSILON {
+				break;
+			}
+		}
+		1.0 - sum * ln_prefix.exp()
+	} else {
+		let tiny = f64::MIN_POSITIVE / EPSILON;
+		let mut b = x + 1.0 - a;
+		let mut c = 1.0 / tiny;
+		let mut d = 1.0 / b;
+		let mut h = d;
+		for i in 1..MAX_ITERATIONS {
+			let an = -(i as f64) * (i as f64 - a);
+			b += 2.0;
+			d = an * d + b;
+			if d.abs() < tiny {
+				d = tiny;
+			}
+			c = b + an / c;
+			if c.abs() < tiny {
+				c = tiny;
+			}
+			d = 1.0 / d;
+			let delta = d * c;
+			h *= delta;
+			if (delta - 1.0).abs() < EPSILON {
+				break;
+			}
+		}
+		h * ln_prefix.exp()
+	}
+}
+
+/// The Lanczos approximation of ln(Γ(x)).
+fn ln_gamma(x: f64) -> f64 {
+	const COEFFICIENTS: [f64; 6] = [
+		76.180_091_729_471_46,
+		-86.505_320_329_416_77,
+		24.014_098_240_830_91,
+		-1.231_739_572_450_155,
+		0.120_865_097_386_617_9e-2,
+		-0.539_523_938_495_3e-5,
+	];
+
+	let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+	let series = COEFFICIENTS
+		.iter()
+		.enumerate()
+		.fold(1.000_000_000_190_015, |sum, (i, c)| sum + c / (x + 1.0 + i as f64));
+	-tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_spanning_trees() {
+		assert_eq!(spanning_trees(1, 5).map(|t| t.len()), Ok(1));
+		assert_eq!(spanning_trees(2, 2).map(|t| t.len()), Ok(4));
+		assert_eq!(spanning_trees(3, 3).map(|t| t.len()), Ok(192));
+	}
+
+	#[test]
+	fn rejects_grids_that_are_empty_or_too_large() {
+		assert!(spanning_trees(0, 3).is_err());
+		assert!(spanning_trees(3, 0).is_err());
+		assert!(spanning_trees(5, 5).is_err());
+		assert!(test(4, 5, 1, 0).is_err());
+	}
+
+	#[test]
+	fn every_algorithm_passes_on_a_small_grid() {
+		let report = test(3, 3, 1920, 0).unwrap();
+		assert!(report.passed(), "\n{}", report);
+	}
+}
@@ -0,0 +1,306 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::ascii;
+use crate::map::{Algorithm, Direction, Map, Position, ALGORITHMS};
+
+/// The result of [`Map::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MazeReport {
+	/// Whether every cell can be reached from every other cell.
+	pub connected: bool,
+	/// Cells that can't be reached from the first cell of the maze.
+	pub unreachable: Vec<Position>,
+	/// Number of independent cycles, passages that could be closed without
+	/// disconnecting anything.
+	pub cycles: usize,
+	/// One of the cycles, as the cells along it.
+	pub sample_cycle: Option<Vec<Position>>,
+}
+
+impl MazeReport {
+	/// A perfect maze has exactly one path between any two cells.
+	pub fn is_perfect(&self) -> bool {
+		self.connected && self.cycles == 0
+	}
+}
+
+impl fmt::Display for MazeReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "Connected: {}", if self.connected { "yes" } else { "no" })?;
+		if !self.unreachable.is_empty() {
+			writeln!(
+				f,
+				"Unreachable cells: {}",
+				self.unreachable
+					.iter()
+					.map(|p| format!("({}, {})", p.0, p.1))
+					.collect::<Vec<_>>()
+					.join(" ")
+			)?;
+		}
+		writeln!(f, "Cycles: {}", self.cycles)?;
+		if let Some(cycle) = &self.sample_cycle {
+			writeln!(
+				f,
+				"Sample cycle: {}",
+				cycle
+					.iter()
+					.map(|p| format!("({}, {})", p.0, p.1))
+					.collect::<Vec<_>>()
+					.join(" ")
+			)?;
+		}
+		write!(f, "Perfect: {}", if self.is_perfect() { "yes" } else { "no" })
+	}
+}
+
+impl Map {
+	pub fn validate(&self) -> MazeReport {
+		let positions: Vec<_> = self.cells().collect();
+
+		let reachable = self.distances(self.ends().0);
+		let unreachable: Vec<_> = positions
+			.iter()
+			.filter(|p| !reachable.contains_key(p))
+			.copied()
+			.collect();
+
+		// Builds a spanning forest one passage at a time, every passage that
+		// connects two cells that are already connected closes a cycle.
+		let mut forest: HashMap<Position, Vec<Position>> = HashMap::new();
+		let mut cycles = 0;
+		let mut sample_cycle = None;
+		for pos in &positions {
+			for dir in &[Direction::Right, Direction::Down] {
+				if self.is(pos, dir) != Some(false) {
+					continue;
+				}
+				let other = self.move_in_direction(pos, dir).unwrap();
+				if let Some(path) = path_in(&forest, *pos, other) {
+					cycles += 1;
+					if sample_cycle.is_none() {
+						sample_cycle = Some(path);
+					}
+				} else {
+					forest.entry(*pos).or_default().push(other);
+					forest.entry(other).or_default().push(*pos);
+				}
+			}
+		}
+
+		MazeReport {
+			connected: unreachable.is_empty(),
+			unreachable,
+			cycles,
+			sample_cycle,
+		}
+	}
+}
+
+/// Finds the path from `from` to `to` in a forest, if they're connected.
+fn path_in(forest: &HashMap<Position, Vec<Position>>, from: Position, to: Position) -> Option<Vec<Position>> {
+	let mut from_to = HashMap::new();
+	from_to.insert(from, from);
+	let mut to_visit = VecDeque::new();
+	to_visit.push_back(from);
+	while let Some(next) = to_visit.pop_front() {
+		if next == to {
+			let mut path = vec![to];
+			let mut current = to;
+			while current != from {
+				current = from_to[&current];
+				path.push(current);
+			}
+			path.reverse();
+			return Some(path);
+		}
+		for moved in forest.get(&next).into_iter().flatten() {
+			from_to.entry(*moved).or_insert_with(|| {
+				to_visit.push_back(*moved);
+				next
+			});
+		}
+	}
+	None
+}
+
+pub struct Failure {
+	pub algorithm: Algorithm,
+	pub rows: usize,
+	pub columns: usize,
+	pub seed: u64,
+	pub report: MazeReport,
+	/// Whether the maze is read back the same from the text it's displayed as,
+	/// and from its ASCII drawing.
+	pub round_trips: bool,
+}
+
+/// Generates `samples` mazes of every size up to `max_size`×`max_size` with
+/// every algorithm, and returns the ones that aren't perfect or can't be read
+/// back from their text.
+pub fn check_generators(max_size: usize, samples: usize, seed: u64) -> (usize, Vec<Failure>) {
+	let mut checked = 0;
+	let mut failures = Vec::new();
+	for algorithm in ALGORITHMS.iter() {
+		for rows in 1..=max_size {
+			for columns in 1..=max_size {
+				for i in 0..samples {
+					let seed = seed.wrapping_add(i as u64);
+					let mut rng = StdRng::seed_from_u64(seed);
+					let start = Position(rows / 2, columns / 2);
+					let map = Map::generate(*algorithm, rows, columns, start, &mut rng, |_| {}, |_, _, _| {});
+					let report = map.validate();
+					let round_trips = Map::from_text(&map.to_string()).is_ok_and(|read| read == map)
+						&& ascii::parse(&ascii::render(&map)).is_ok_and(|read| read.map == map);
+					checked += 1;
+					if !report.is_perfect() || !round_trips {
+						failures.push(Failure {
+							algorithm: *algorithm,
+							rows,
+							columns,
+							seed,
+							report,
+							round_trips,
+						});
+					}
+				}
+			}
+		}
+	}
+	(checked, failures)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::delta::Delta;
+	use crate::grid::{Grid, Shape};
+	use crate::hex::Hex;
+	use crate::levels::Levels;
+	use crate::map::Wrap;
+	use crate::mask::Mask;
+	use crate::polar::Polar;
+
+	#[test]
+	fn every_generator_makes_perfect_mazes() {
+		let (checked, failures) = check_generators(6, 3, 0);
+		assert_eq!(checked, ALGORITHMS.len() * 6 * 6 * 3);
+		for failure in &failures {
+			eprintln!(
+				"{} {}×{} with seed {}:\n{}",
+				failure.algorithm.name(),
+				failure.rows,
+				failure.columns,
+				failure.seed,
+				failure.report
+			);
+		}
+		assert!(failures.is_empty());
+	}
+
+	#[test]
+	fn masked_mazes_are_perfect() {
+		// A diamond, a ring and a U, the last two of which cut the rectangle
+		// into parts for the algorithms that work on all of it.
+		let masks = [
+			"..#..\n.###.\n#####\n.###.\n..#..\n",
+			"#####\n#...#\n#...#\n#####\n",
+			"#..#\n#..#\n#..#\n####\n",
+		];
+		for mask in &masks {
+			let mask = Mask::parse(mask.as_bytes()).unwrap();
+			for algorithm in ALGORITHMS.iter() {
+				for seed in 0..5 {
+					let blank = Map::with_mask(mask.clone());
+					let start = blank.ends().0;
+					let mut rng = StdRng::seed_from_u64(seed);
+					let map = Map::generate_in(*algorithm, blank, start, &mut rng, |_| {}, |_, _, _| {});
+					let report = map.validate();
+					assert!(
+						report.is_perfect(),
+						"{} with seed {}:\n{}",
+						algorithm.name(),
+						seed,
+						report
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn wrapped_mazes_are_perfect() {
+		for wrap in &[Wrap::Horizontal, Wrap::Both] {
+			for algorithm in ALGORITHMS.iter() {
+				for (rows, columns) in &[(3, 3), (4, 6), (7, 5)] {
+					for seed in 0..5 {
+						let blank = Map::wrapping(*rows, *columns, *wrap).unwrap();
+						let mut rng = StdRng::seed_from_u64(seed);
+						let map = Map::generate_in(*algorithm, blank, Position(0, 0), &mut rng, |_| {}, |_, _, _| {});
+						let report = map.validate();
+						assert!(
+							report.is_perfect(),
+							"{} {:?} {}×{} with seed {}:\n{}",
+							algorithm.name(),
+							wrap,
+							rows,
+							columns,
+							seed,
+							report
+						);
+					}
+				}
+			}
+		}
+	}
+
+	/// Generates grids of the shape with every algorithm that supports them,
+	/// and checks that each is a spanning tree of the cells.
+	fn check_grid<S: Shape>(shape: impl Fn() -> S) {
+		for algorithm in ALGORITHMS.iter() {
+			for seed in 0..5 {
+				let mut rng = StdRng::seed_from_u64(seed);
+				let grid = match Grid::generate(*algorithm, shape(), 0, &mut rng) {
+					Ok(grid) => grid,
+					Err(_) => {
+						assert!(matches!(algorithm, Algorithm::Tree | Algorithm::Division));
+						continue;
+					}
+				};
+				let count = grid.shape.count();
+				let passages: usize = (0..count).map(|cell| grid.openings(cell).len()).sum::<usize>() / 2;
+				assert!(
+					grid.distances(0).iter().all(|d| d.is_some()),
+					"{} with seed {} isn't connected",
+					algorithm.name(),
+					seed
+				);
+				assert_eq!(
+					passages,
+					count - 1,
+					"{} with seed {} has cycles",
+					algorithm.name(),
+					seed
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn grids_of_every_shape_are_spanning_trees() {
+		check_grid(|| Hex { rows: 5, columns: 6 });
+		check_grid(|| Polar::new(5).unwrap());
+		check_grid(|| Delta::new(5, 8).unwrap());
+		check_grid(|| Levels {
+			levels: 3,
+			rows: 4,
+			columns: 3,
+		});
+	}
+}